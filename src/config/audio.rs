@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    /// Volume of every sound, from silent at `0.0` to full at `1.0`.
+    pub volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self { volume: 1.0 }
+    }
+}
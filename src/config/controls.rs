@@ -1,24 +1,37 @@
-use bevy::prelude::{KeyCode, MouseButton};
-use enum_iterator::Sequence;
+use std::fmt;
+
+use bevy::prelude::{
+    Axis, GamepadAxis, GamepadAxisType, GamepadButton, GamepadButtonType, Gamepads, Input, KeyCode,
+    MouseButton,
+};
+use enum_iterator::{all, Sequence};
 use serde::{Deserialize, Serialize};
 
+use super::StickSettings;
+
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct ControlBindings {
-    pub forward: UserInput,
-    pub backward: UserInput,
-    pub left: UserInput,
-    pub right: UserInput,
-    pub jump: UserInput,
-    pub crouch: UserInput,
-    pub sprint: UserInput,
-    pub ram: UserInput,
-    pub pour: UserInput,
-    pub load: UserInput,
-    pub fire: UserInput,
+    pub forward: Binding,
+    pub backward: Binding,
+    pub left: Binding,
+    pub right: Binding,
+    pub jump: Binding,
+    pub crouch: Binding,
+    pub sprint: Binding,
+    pub ram: Binding,
+    pub pour: Binding,
+    pub load: Binding,
+    pub fire: Binding,
+    pub aim: Binding,
+    pub melee: Binding,
+    /// Shows the scoreboard while held.
+    pub scoreboard: Binding,
     pub pointer_sensitivity: f32,
+    pub gamepad: StickSettings,
 }
 
-#[derive(Clone, Copy, Sequence, Debug)]
+#[derive(Clone, Copy, Sequence, Debug, PartialEq, Eq)]
 pub enum UserAction {
     MoveForward,
     MoveBackward,
@@ -31,12 +44,206 @@ pub enum UserAction {
     Pour,
     Load,
     Fire,
+    Aim,
+    Melee,
+    Scoreboard,
 }
 
-#[derive(Serialize, Deserialize, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 pub enum UserInput {
     Keyboard(KeyCode),
     Mouse(MouseButton),
+    /// A button on any connected gamepad.
+    GamepadButton(GamepadButtonType),
+    /// An axis of any connected gamepad, pushed more than halfway in one direction.
+    GamepadAxis {
+        axis: GamepadAxisType,
+        positive: bool,
+    },
+}
+
+/// The state of every input device a `UserInput` can be read from.
+pub struct InputDevices<'a> {
+    pub keyboard: &'a Input<KeyCode>,
+    pub mouse: &'a Input<MouseButton>,
+    pub gamepads: &'a Gamepads,
+    pub gamepad_buttons: &'a Input<GamepadButton>,
+    pub gamepad_axes: &'a Axis<GamepadAxis>,
+}
+
+impl UserInput {
+    /// How far an axis must be pushed before it counts as pressed.
+    const AXIS_THRESHOLD: f32 = 0.5;
+
+    /// Checks if this input is currently held down.
+    pub fn pressed(&self, devices: &InputDevices) -> bool {
+        match *self {
+            Self::Keyboard(key) => devices.keyboard.pressed(key),
+            Self::Mouse(button) => devices.mouse.pressed(button),
+            Self::GamepadButton(button_type) => devices.gamepads.iter().any(|gamepad| {
+                devices
+                    .gamepad_buttons
+                    .pressed(GamepadButton::new(gamepad, button_type))
+            }),
+            Self::GamepadAxis { axis, positive } => devices.gamepads.iter().any(|gamepad| {
+                let value = devices
+                    .gamepad_axes
+                    .get(GamepadAxis::new(gamepad, axis))
+                    .unwrap_or(0.0);
+
+                if positive {
+                    value > Self::AXIS_THRESHOLD
+                } else {
+                    value < -Self::AXIS_THRESHOLD
+                }
+            }),
+        }
+    }
+}
+
+impl fmt::Display for UserInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Keyboard(key) => write!(f, "{key:?}"),
+            Self::Mouse(MouseButton::Other(button)) => write!(f, "Mouse {button}"),
+            Self::Mouse(button) => write!(f, "Mouse {button:?}"),
+            Self::GamepadButton(GamepadButtonType::Other(button)) => write!(f, "Pad {button}"),
+            Self::GamepadButton(button) => write!(f, "Pad {button:?}"),
+            Self::GamepadAxis { axis, positive } => {
+                let direction = if *positive { '+' } else { '-' };
+                write!(f, "Pad {axis:?}{direction}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for UserAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::MoveForward => "Move Forward",
+            Self::MoveBackward => "Move Backward",
+            Self::MoveLeft => "Move Left",
+            Self::MoveRight => "Move Right",
+            Self::Jump => "Jump",
+            Self::Crouch => "Crouch",
+            Self::Sprint => "Sprint",
+            Self::Ram => "Ram",
+            Self::Pour => "Pour",
+            Self::Load => "Load",
+            Self::Fire => "Fire",
+            Self::Aim => "Aim",
+            Self::Melee => "Melee",
+            Self::Scoreboard => "Scoreboard",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// One of the two inputs which can be bound to each `UserAction`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingSlot {
+    Primary,
+    Secondary,
+}
+
+/// The inputs which perform a `UserAction`. Either input can be left unbound.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(from = "BindingFormat")]
+pub struct Binding {
+    pub primary: Option<UserInput>,
+    pub secondary: Option<UserInput>,
+}
+
+/// Formats a `Binding` can be read from. Older settings files bound a single input to each action.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BindingFormat {
+    Single(UserInput),
+    Full {
+        primary: Option<UserInput>,
+        #[serde(default)]
+        secondary: Option<UserInput>,
+    },
+}
+
+impl From<BindingFormat> for Binding {
+    fn from(value: BindingFormat) -> Self {
+        match value {
+            BindingFormat::Single(input) => input.into(),
+            BindingFormat::Full { primary, secondary } => Self { primary, secondary },
+        }
+    }
+}
+
+impl Binding {
+    pub fn get(&self, slot: BindingSlot) -> Option<UserInput> {
+        match slot {
+            BindingSlot::Primary => self.primary,
+            BindingSlot::Secondary => self.secondary,
+        }
+    }
+
+    fn get_mut(&mut self, slot: BindingSlot) -> &mut Option<UserInput> {
+        match slot {
+            BindingSlot::Primary => &mut self.primary,
+            BindingSlot::Secondary => &mut self.secondary,
+        }
+    }
+
+    /// Finds which slot, if any, `input` is bound to.
+    pub fn slot_of(&self, input: UserInput) -> Option<BindingSlot> {
+        [BindingSlot::Primary, BindingSlot::Secondary]
+            .into_iter()
+            .find(|slot| self.get(*slot) == Some(input))
+    }
+
+    /// Checks if either input is currently held down.
+    pub fn pressed(&self, devices: &InputDevices) -> bool {
+        [self.primary, self.secondary]
+            .iter()
+            .flatten()
+            .any(|input| input.pressed(devices))
+    }
+
+    /// Adds a secondary input to this binding.
+    fn or(self, secondary: impl Into<UserInput>) -> Self {
+        Self {
+            secondary: Some(secondary.into()),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.primary, self.secondary) {
+            (Some(primary), Some(secondary)) => write!(f, "{primary} / {secondary}"),
+            (Some(input), None) | (None, Some(input)) => write!(f, "{input}"),
+            (None, None) => f.write_str("Unbound"),
+        }
+    }
+}
+
+impl From<UserInput> for Binding {
+    fn from(value: UserInput) -> Self {
+        Self {
+            primary: Some(value),
+            secondary: None,
+        }
+    }
+}
+
+impl From<KeyCode> for Binding {
+    fn from(value: KeyCode) -> Self {
+        UserInput::from(value).into()
+    }
+}
+
+impl From<MouseButton> for Binding {
+    fn from(value: MouseButton) -> Self {
+        UserInput::from(value).into()
+    }
 }
 
 impl From<KeyCode> for UserInput {
@@ -45,6 +252,12 @@ impl From<KeyCode> for UserInput {
     }
 }
 
+impl From<GamepadButtonType> for UserInput {
+    fn from(value: GamepadButtonType) -> Self {
+        Self::GamepadButton(value)
+    }
+}
+
 impl From<MouseButton> for UserInput {
     fn from(value: MouseButton) -> Self {
         Self::Mouse(value)
@@ -58,21 +271,25 @@ impl Default for ControlBindings {
             backward: KeyCode::S.into(),
             left: KeyCode::A.into(),
             right: KeyCode::D.into(),
-            jump: KeyCode::Space.into(),
-            crouch: KeyCode::LControl.into(),
-            sprint: KeyCode::LShift.into(),
-            ram: KeyCode::R.into(),
-            pour: KeyCode::F.into(),
-            load: KeyCode::V.into(),
-            fire: MouseButton::Left.into(),
+            jump: Binding::from(KeyCode::Space).or(GamepadButtonType::South),
+            crouch: Binding::from(KeyCode::LControl).or(GamepadButtonType::East),
+            sprint: Binding::from(KeyCode::LShift).or(GamepadButtonType::LeftThumb),
+            ram: Binding::from(KeyCode::R).or(GamepadButtonType::West),
+            pour: Binding::from(KeyCode::F).or(GamepadButtonType::DPadUp),
+            load: Binding::from(KeyCode::V).or(GamepadButtonType::North),
+            fire: Binding::from(MouseButton::Left).or(GamepadButtonType::RightTrigger2),
+            aim: Binding::from(MouseButton::Right).or(GamepadButtonType::LeftTrigger2),
+            melee: Binding::from(KeyCode::E).or(GamepadButtonType::RightThumb),
+            scoreboard: Binding::from(KeyCode::Tab).or(GamepadButtonType::Select),
             pointer_sensitivity: 0.5,
+            gamepad: StickSettings::default(),
         }
     }
 }
 
 impl ControlBindings {
-    /// Returns a `UserInput` bindings in the provided `UserAction`.
-    pub fn input_for<'a>(&'a self, action: UserAction) -> &'a UserInput {
+    /// Returns the `Binding` of the provided `UserAction`.
+    pub fn binding_for(&self, action: UserAction) -> &Binding {
         match action {
             UserAction::MoveForward => &self.forward,
             UserAction::MoveBackward => &self.backward,
@@ -85,6 +302,133 @@ impl ControlBindings {
             UserAction::Pour => &self.pour,
             UserAction::Load => &self.load,
             UserAction::Fire => &self.fire,
+            UserAction::Aim => &self.aim,
+            UserAction::Melee => &self.melee,
+            UserAction::Scoreboard => &self.scoreboard,
+        }
+    }
+
+    fn binding_for_mut(&mut self, action: UserAction) -> &mut Binding {
+        match action {
+            UserAction::MoveForward => &mut self.forward,
+            UserAction::MoveBackward => &mut self.backward,
+            UserAction::MoveLeft => &mut self.left,
+            UserAction::MoveRight => &mut self.right,
+            UserAction::Jump => &mut self.jump,
+            UserAction::Crouch => &mut self.crouch,
+            UserAction::Sprint => &mut self.sprint,
+            UserAction::Ram => &mut self.ram,
+            UserAction::Pour => &mut self.pour,
+            UserAction::Load => &mut self.load,
+            UserAction::Fire => &mut self.fire,
+            UserAction::Aim => &mut self.aim,
+            UserAction::Melee => &mut self.melee,
+            UserAction::Scoreboard => &mut self.scoreboard,
         }
     }
+
+    /// Finds the action and slot `input` is bound to, if any.
+    pub fn action_for(&self, input: UserInput) -> Option<(UserAction, BindingSlot)> {
+        all::<UserAction>().find_map(|action| {
+            self.binding_for(action)
+                .slot_of(input)
+                .map(|slot| (action, slot))
+        })
+    }
+
+    /// Binds `input` to `slot` of `action`, or clears the slot if `input` is `None`.
+    ///
+    /// An input can only perform one action, so if `input` was already bound elsewhere it is
+    /// swapped with whatever `slot` was bound to. Returns the other action affected by the swap.
+    pub fn rebind(
+        &mut self,
+        action: UserAction,
+        slot: BindingSlot,
+        input: Option<UserInput>,
+    ) -> Option<UserAction> {
+        let previous = self.binding_for(action).get(slot);
+
+        if previous == input {
+            return None;
+        }
+
+        let conflict = input.and_then(|input| self.action_for(input));
+
+        *self.binding_for_mut(action).get_mut(slot) = input;
+
+        let (other_action, other_slot) = conflict?;
+        *self.binding_for_mut(other_action).get_mut(other_slot) = previous;
+
+        // Swapping the primary and secondary inputs of one action doesn't affect any other
+        (other_action != action).then_some(other_action)
+    }
+
+    /// Restores every binding to its default, leaving the other control settings alone.
+    pub fn reset_bindings(&mut self) {
+        *self = Self {
+            pointer_sensitivity: self.pointer_sensitivity,
+            gamepad: self.gamepad,
+            ..Self::default()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_a_bound_input_swaps_it() {
+        let mut controls = ControlBindings::default();
+
+        let swapped = controls.rebind(
+            UserAction::Jump,
+            BindingSlot::Primary,
+            Some(KeyCode::W.into()),
+        );
+
+        assert_eq!(swapped, Some(UserAction::MoveForward));
+        assert_eq!(controls.jump.primary, Some(KeyCode::W.into()));
+        assert_eq!(controls.forward.primary, Some(KeyCode::Space.into()));
+    }
+
+    #[test]
+    fn secondary_bindings_are_pressed_too() {
+        let mut controls = ControlBindings::default();
+        controls.rebind(
+            UserAction::Jump,
+            BindingSlot::Secondary,
+            Some(MouseButton::Middle.into()),
+        );
+
+        let mut mouse = Input::<MouseButton>::default();
+        mouse.press(MouseButton::Middle);
+
+        let devices = InputDevices {
+            keyboard: &Input::default(),
+            mouse: &mouse,
+            gamepads: &Gamepads::default(),
+            gamepad_buttons: &Input::default(),
+            gamepad_axes: &Axis::default(),
+        };
+
+        assert!(controls.jump.pressed(&devices));
+        assert_eq!(
+            controls.action_for(MouseButton::Middle.into()),
+            Some((UserAction::Jump, BindingSlot::Secondary))
+        );
+
+        controls.reset_bindings();
+        assert_eq!(controls.jump, ControlBindings::default().jump);
+    }
+
+    #[test]
+    fn single_input_bindings_can_still_be_loaded() {
+        let binding: Binding = serde_json::from_str(r#"{"Keyboard":"W"}"#).unwrap();
+        assert_eq!(binding, KeyCode::W.into());
+
+        let binding: Binding =
+            serde_json::from_str(r#"{"primary":{"Mouse":"Left"},"secondary":null}"#).unwrap();
+        assert_eq!(binding, MouseButton::Left.into());
+    }
 }
@@ -0,0 +1,72 @@
+use bevy::prelude::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// How gamepad sticks are read. The left stick moves and the right stick looks around.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StickSettings {
+    /// Fraction of a stick's travel from the centre which is ignored, to hide drift.
+    pub deadzone: f32,
+    /// Exponent applied to the deflection past the deadzone. Values above `1.0` give finer
+    /// control near the centre, while `1.0` is linear.
+    pub response_curve: f32,
+    /// Turning speed in radians per second with the look stick fully deflected.
+    pub look_speed: f32,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.15,
+            response_curve: 2.0,
+            look_speed: 3.0,
+        }
+    }
+}
+
+impl StickSettings {
+    /// Largest deadzone allowed, so a fully deflected stick always registers.
+    const MAX_DEADZONE: f32 = 0.95;
+
+    /// Applies the deadzone and response curve to the raw position of a stick.
+    pub fn apply(&self, raw: Vec2) -> Vec2 {
+        let deadzone = self.deadzone.clamp(0.0, Self::MAX_DEADZONE);
+        let magnitude = raw.length();
+
+        if magnitude <= deadzone {
+            return Vec2::ZERO;
+        }
+
+        // Rescale so the output rises from zero at the edge of the deadzone
+        let deflection = ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0);
+
+        raw / magnitude * deflection.powf(self.response_curve.max(0.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadzone_ignores_small_deflections() {
+        let settings = StickSettings::default();
+
+        assert_eq!(settings.apply(Vec2::new(0.1, -0.05)), Vec2::ZERO);
+        assert_eq!(settings.apply(Vec2::X), Vec2::X);
+    }
+
+    #[test]
+    fn response_curve_shapes_partial_deflections() {
+        let settings = StickSettings {
+            deadzone: 0.0,
+            response_curve: 2.0,
+            ..StickSettings::default()
+        };
+
+        let output = settings.apply(Vec2::new(0.0, 0.5));
+
+        assert!((output.y - 0.25).abs() < 1e-6);
+        assert_eq!(output.x, 0.0);
+    }
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraSettings {
+    /// Vertical field of view in degrees.
+    pub fov: f32,
+    /// Scales the amount of view bobbing, where `0.0` disables it entirely.
+    pub view_bob: f32,
+    /// Height of the eyes as a fraction of the player's total height.
+    pub eye_height: f32,
+    /// Magnification applied to the field of view while aiming down the sights.
+    pub aim_zoom: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            fov: 72.0,
+            view_bob: 1.0,
+            eye_height: 0.95,
+            aim_zoom: 1.5,
+        }
+    }
+}
+
+impl CameraSettings {
+    /// Vertical field of view in radians.
+    pub fn fov_radians(&self) -> f32 {
+        self.fov.to_radians()
+    }
+}
@@ -1,9 +1,11 @@
-use bevy::window::WindowMode;
+use bevy::window::{PresentMode, WindowMode};
 use serde::{Deserialize, Serialize};
 
+pub use camera::*;
 pub use msaa::*;
 pub use particles::*;
 
+mod camera;
 mod msaa;
 mod particles;
 
@@ -15,6 +17,8 @@ pub struct GraphicsSettings {
     pub height: usize,
     pub msaa: Msaa,
     pub particles: ParticleDetail,
+    #[serde(default)]
+    pub camera: CameraSettings,
 }
 
 impl Default for GraphicsSettings {
@@ -26,6 +30,18 @@ impl Default for GraphicsSettings {
             height: 720,
             msaa: Msaa::X4,
             particles: ParticleDetail::High,
+            camera: CameraSettings::default(),
+        }
+    }
+}
+
+impl GraphicsSettings {
+    /// How frames are presented to the window, depending on whether vsync is enabled.
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
         }
     }
 }
@@ -2,11 +2,19 @@ use std::num::NonZeroUsize;
 
 use serde::{Deserialize, Serialize};
 
+use crate::team::{FriendlyFire, GameMode};
+
 #[derive(Serialize, Deserialize)]
 pub struct MatchMakingSettings {
     pub server: String,
     pub room: String,
     pub players: NonZeroUsize,
+    /// Game mode played when this peer hosts a match.
+    #[serde(default)]
+    pub mode: GameMode,
+    /// Friendly-fire policy used when this peer hosts a match.
+    #[serde(default)]
+    pub friendly_fire: FriendlyFire,
 }
 
 impl Default for MatchMakingSettings {
@@ -15,6 +23,8 @@ impl Default for MatchMakingSettings {
             server: "wss://matchbox-muskrats.fly.dev:443".to_owned(),
             room: "default_room".to_owned(),
             players: NonZeroUsize::new(4).unwrap(),
+            mode: GameMode::default(),
+            friendly_fire: FriendlyFire::default(),
         }
     }
 }
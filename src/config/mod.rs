@@ -6,22 +6,31 @@ use std::{
     io::{BufReader, BufWriter},
 };
 
+mod audio;
 mod controls;
+mod gamepad;
 mod graphics;
 mod logging;
 mod matchmaking;
+mod movement;
 
+pub use audio::*;
 pub use controls::*;
+pub use gamepad::*;
 pub use graphics::*;
 pub use logging::*;
 pub use matchmaking::*;
+pub use movement::*;
 
 #[derive(Serialize, Deserialize, Default, Resource)]
+#[serde(default)]
 pub struct Config {
     pub matchmaking: MatchMakingSettings,
+    pub movement: MovementSettings,
     pub controls: ControlBindings,
     pub graphics: GraphicsSettings,
     pub logging: LoggingSettings,
+    pub audio: AudioSettings,
 }
 
 impl Config {
@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::controller::MovementProfile;
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct MovementSettings {
+    /// Name of the profile every player uses when this peer hosts a match.
+    pub profile: String,
+    pub profiles: HashMap<String, MovementProfile>,
+}
+
+impl Default for MovementSettings {
+    fn default() -> Self {
+        let mut profiles = HashMap::new();
+
+        profiles.insert("infantry".to_owned(), MovementProfile::infantry());
+        profiles.insert("skirmisher".to_owned(), MovementProfile::skirmisher());
+
+        Self {
+            profile: "infantry".to_owned(),
+            profiles,
+        }
+    }
+}
+
+impl MovementSettings {
+    /// Returns the selected `MovementProfile`, falling back to the default if it doesn't exist.
+    pub fn selected_profile(&self) -> MovementProfile {
+        match self.profiles.get(&self.profile) {
+            Some(profile) => profile.clone(),
+            None => {
+                log::warn!("Movement profile '{}' does not exist", self.profile);
+                MovementProfile::default()
+            }
+        }
+    }
+}
@@ -3,32 +3,35 @@ use bevy_rapier3d::prelude::*;
 
 use super::{FpsController, FpsControllerInput, FreeLookState};
 
-/// System responsible for transforming player cameras onto their controllers.
+/// Eye height ratio used by the simulation, such as when firing.
+/// Unlike the camera settings this must be identical for every peer.
+pub const SIMULATED_EYE_HEIGHT: f32 = 0.95;
+
+/// Finds the height of the eyes above the feet of a player's capsule `collider`,
+/// where `ratio` is the fraction of the capsule's total height the eyes are placed at.
+pub fn eye_height(collider: &Collider, ratio: f32) -> Option<f32> {
+    let capsule = collider.as_capsule()?;
+
+    Some((capsule.segment().b().y + capsule.radius()) * ratio)
+}
+
+/// System responsible for turning players and their cameras to face where their controllers look.
+///
+/// Only orientation is simulated. Cameras are placed at the configured eye height outside the
+/// rollback schedule, by `animate_viewmodels`.
 pub fn map_camera_transform(
     mut player_query: Query<
-        (
-            &mut Transform,
-            &Collider,
-            &FpsController,
-            &FpsControllerInput,
-        ),
+        (&mut Transform, &FpsController, &FpsControllerInput),
         With<FpsController>,
     >,
     mut camera_query: Query<(&mut Transform, &Parent), (With<Camera>, Without<FpsController>)>,
 ) {
     for (mut camera_transform, player) in camera_query.iter_mut() {
-        let Ok((mut player_transform, collider, controller, input)) = player_query.get_mut(player.get()) else {
+        let Ok((mut player_transform, controller, input)) = player_query.get_mut(player.get())
+        else {
             continue;
         };
 
-        let Some(capsule) = collider.as_capsule() else {
-            continue;
-        };
-
-        // TODO: let this be more configurable
-        let camera_height = capsule.segment().b().y + capsule.radius() * 0.75;
-        camera_transform.translation = Vec3::Y * camera_height;
-
         let (_, player_pitch, player_roll) = player_transform.rotation.to_euler(EulerRot::YXZ);
         let (camera_yaw, _, camera_roll) = camera_transform.rotation.to_euler(EulerRot::YXZ);
 
@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+
+/// Events published by an `FpsController` as its player moves around.
+pub enum FpsControllerEvent {
+    /// The player has covered a stride along the ground.
+    Footstep { entity: Entity },
+    /// The player has jumped off the ground.
+    Jumped { entity: Entity },
+    /// The player has landed on the ground while falling at `impact_speed`.
+    Landed { entity: Entity, impact_speed: f32 },
+    /// The player has started crouching.
+    Crouched { entity: Entity },
+    /// The player has finished standing back up.
+    Uncrouched { entity: Entity },
+}
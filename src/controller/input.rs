@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 
 /// Represents if the player is using the free-look feature.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Reflect, FromReflect)]
 pub enum FreeLookState {
     /// The player is not using free-look (default)
     Not,
@@ -14,12 +14,15 @@ pub enum FreeLookState {
 }
 
 /// Component describing desired player inputs in a device-agnostic way.
-#[derive(Component, Default)]
+///
+/// Orientation accumulates here and recoil kicks it, so it is rolled back with the simulation.
+#[derive(Component, Default, Reflect)]
 pub struct FpsControllerInput {
     pub fly: bool,
     pub sprint: bool,
     pub jump: bool,
     pub crouch: bool,
+    pub aim: bool,
     pub free_look: FreeLookState,
     pub pitch: f32,
     pub yaw: f32,
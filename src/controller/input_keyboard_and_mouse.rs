@@ -40,29 +40,14 @@ pub fn map_player_input_to_controller_input(
             controller_input.yaw = controller_input.yaw.rem_euclid(TAU);
         }
 
-        // Map button inputs to controller translation
-        let x = match (
-            player_input.buttons.get(UserAction::MoveRight),
-            player_input.buttons.get(UserAction::MoveLeft),
-        ) {
-            (true, false) => 1.0,
-            (false, true) => -1.0,
-            _ => 0.0,
-        };
-
-        let z = match (
-            player_input.buttons.get(UserAction::MoveForward),
-            player_input.buttons.get(UserAction::MoveBackward),
-        ) {
-            (true, false) => 1.0,
-            (false, true) => -1.0,
-            _ => 0.0,
-        };
+        // Movement keys and analog sticks are combined into a single axis when captured
+        let movement: Vec2 = player_input.movement.into();
 
-        controller_input.movement = Vec3::new(x, 0.0, z);
+        controller_input.movement = Vec3::new(movement.x, 0.0, movement.y);
 
         controller_input.sprint = player_input.buttons.get(UserAction::Sprint);
         controller_input.jump = player_input.buttons.get(UserAction::Jump);
         controller_input.crouch = player_input.buttons.get(UserAction::Crouch);
+        controller_input.aim = player_input.buttons.get(UserAction::Aim);
     }
 }
@@ -1,15 +1,19 @@
 mod bundle;
 mod camera_controller;
+mod events;
 mod input;
 mod input_keyboard_and_mouse;
 mod movement;
 mod player;
+mod profile;
 mod set;
 
 pub use bundle::FpsControllerBundle;
-pub use camera_controller::map_camera_transform;
+pub use camera_controller::{eye_height, map_camera_transform, SIMULATED_EYE_HEIGHT};
+pub use events::FpsControllerEvent;
 pub use input::{FpsControllerInput, FreeLookState};
 pub use input_keyboard_and_mouse::map_player_input_to_controller_input;
 pub use movement::{map_input_movement, map_input_orientation, MoveMode};
 pub use player::FpsController;
+pub use profile::MovementProfile;
 pub use set::FpsControllerSet;
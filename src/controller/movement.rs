@@ -1,7 +1,12 @@
 use bevy::{math::Vec3Swizzles, prelude::*};
 use bevy_rapier3d::prelude::*;
 
-use super::{FpsController, FpsControllerInput, FreeLookState};
+use crate::water::WaterVolume;
+
+use super::{
+    eye_height, FpsController, FpsControllerEvent, FpsControllerInput, FreeLookState,
+    SIMULATED_EYE_HEIGHT,
+};
 
 /// Component describing the current movement mode of a player.
 #[derive(Component, PartialEq)]
@@ -10,6 +15,8 @@ pub enum MoveMode {
     Noclip,
     /// Standard ground-based movement.
     Ground,
+    /// Movement through a body of water.
+    Swimming,
 }
 
 impl Default for MoveMode {
@@ -35,19 +42,37 @@ pub fn map_input_orientation(mut query: Query<(&mut FpsController, &mut FpsContr
 pub fn map_input_movement(
     time: Res<Time>,
     physics_context: Res<RapierContext>,
+    water_volumes: Query<&WaterVolume>,
+    mut events: EventWriter<FpsControllerEvent>,
     mut query: Query<(
         Entity,
         &FpsControllerInput,
-        &MoveMode,
+        &mut MoveMode,
         &mut FpsController,
         &mut Collider,
         &mut Transform,
         &mut Velocity,
     )>,
 ) {
-    for (entity, input, move_mode, mut controller, mut collider, mut transform, mut velocity) in
+    for (entity, input, mut move_mode, mut controller, mut collider, mut transform, mut velocity) in
         query.iter_mut()
     {
+        controller.submersion = water_submersion(
+            &physics_context,
+            |entity| water_volumes.get(entity).ok().map(|water| water.surface),
+            &controller,
+            &collider,
+            &transform,
+        );
+
+        if *move_mode == MoveMode::Ground && controller.submersion > controller.swim_submersion {
+            *move_mode = MoveMode::Swimming;
+        } else if *move_mode == MoveMode::Swimming
+            && controller.submersion <= controller.swim_submersion
+        {
+            *move_mode = MoveMode::Ground;
+        }
+
         match *move_mode {
             MoveMode::Noclip => {
                 noclip_movement(input, &mut controller, &mut velocity);
@@ -62,12 +87,53 @@ pub fn map_input_movement(
                     &mut collider,
                     &mut transform,
                     &mut velocity,
+                    &mut events,
                 );
             }
+            MoveMode::Swimming => {
+                swim_movement(input, &mut controller, &mut velocity, time.delta_seconds());
+            }
         }
     }
 }
 
+/// Measures how deep the player is in water, as a fraction of the height of their eyes.
+/// `water_surface` finds the height of the surface of an entity, if it is a body of water.
+fn water_submersion(
+    physics_context: &RapierContext,
+    water_surface: impl Fn(Entity) -> Option<f32>,
+    controller: &FpsController,
+    collider: &Collider,
+    transform: &Transform,
+) -> f32 {
+    // The same eyes as the rest of the simulation, which are lowered by crouching
+    let Some(eye_height) = eye_height(collider, SIMULATED_EYE_HEIGHT) else {
+        return 0.0;
+    };
+
+    let is_water = |entity: Entity| water_surface(entity).is_some();
+    let filter = QueryFilter::default().exclude_solids().predicate(&is_water);
+
+    // Sample just above the feet so standing on the bottom of a pool still counts
+    let mut surface: Option<f32> = None;
+    physics_context.intersections_with_point(
+        transform.translation + Vec3::Y * controller.radius,
+        filter,
+        |entity| {
+            if let Some(water) = water_surface(entity) {
+                surface = Some(surface.map_or(water, |height| height.max(water)));
+            }
+            true
+        },
+    );
+
+    let Some(surface) = surface else {
+        return 0.0;
+    };
+
+    ((surface - transform.translation.y) / eye_height).clamp(0.0, 1.0)
+}
+
 /// Subsystem responsible for controlling the player when in no-clip mode.
 fn noclip_movement(
     input: &FpsControllerInput,
@@ -94,6 +160,52 @@ fn noclip_movement(
     }
 }
 
+/// Subsystem responsible for controlling the player when swimming through water.
+fn swim_movement(
+    input: &FpsControllerInput,
+    controller: &mut FpsController,
+    velocity: &mut Velocity,
+    dt: f32,
+) {
+    controller.ground_tick = 0;
+    controller.sliding = false;
+
+    let swim_speed = if input.sprint {
+        controller.fast_swim_speed
+    } else {
+        controller.swim_speed
+    };
+
+    // Swim towards where the player is looking, rising with jump and diving with crouch
+    let mut move_to_world = Mat3::from_euler(EulerRot::YXZ, controller.yaw, controller.pitch, 0.0);
+    move_to_world.z_axis *= -1.0; // Forward is -Z
+    let vertical = match (input.jump, input.crouch) {
+        (true, false) => 1.0,
+        (false, true) => -1.0,
+        _ => 0.0,
+    };
+    let mut wish_direction = move_to_world * input.movement + Vec3::Y * vertical;
+    let wish_length = wish_direction.length();
+    if wish_length > f32::EPSILON {
+        wish_direction /= wish_length;
+    }
+    let wish_speed = swim_speed * f32::min(wish_length, 1.0);
+
+    velocity.linvel += acceleration(
+        wish_direction,
+        wish_speed,
+        controller.swim_acceleration,
+        velocity.linvel,
+        dt,
+    );
+
+    // Water resists motion in every direction
+    velocity.linvel *= f32::max(1.0 - controller.water_drag * dt, 0.0);
+
+    // Buoyancy pushes harder the deeper the player is, floating them near the surface
+    velocity.linvel.y += (controller.buoyancy * controller.submersion - controller.gravity) * dt;
+}
+
 /// Subsystem responsible for controlling the player when in standard ground-based movement mode.
 fn ground_movement(
     time: &Res<Time>,
@@ -104,9 +216,14 @@ fn ground_movement(
     collider: &mut Collider,
     transform: &mut Transform,
     velocity: &mut Velocity,
+    events: &mut EventWriter<FpsControllerEvent>,
 ) {
     let dt = time.delta_seconds();
 
+    if !input.jump {
+        controller.jumping = false;
+    }
+
     let Some(capsule) = collider.as_capsule() else {
         return;
     };
@@ -119,8 +236,10 @@ fn ground_movement(
         capsule.segment.b.into(),
         capsule.radius * 0.9,
     );
-    // Avoid self collisions
-    let filter = QueryFilter::default().exclude_rigid_body(entity);
+    // Avoid self collisions and bodies of water
+    let filter = QueryFilter::default()
+        .exclude_rigid_body(entity)
+        .exclude_sensors();
     let ground_cast = physics_context.cast_shape(
         transform.translation,
         transform.rotation,
@@ -141,16 +260,55 @@ fn ground_movement(
     }
     let max_speed = if input.crouch {
         controller.crouched_speed
+    } else if input.aim {
+        controller.aim_speed
     } else if input.sprint {
         controller.run_speed
     } else {
         controller.walk_speed
     };
-    wish_speed = f32::min(wish_speed, max_speed);
+    // Partially deflected sticks move proportionally slower
+    wish_speed = f32::min(
+        wish_speed,
+        max_speed * f32::min(input.movement.length(), 1.0),
+    );
+
+    let sliding = ground_cast.map_or(false, |(_, toi)| {
+        controller.slide_enabled
+            && Vec3::dot(toi.normal1, Vec3::Y) <= controller.traction_normal_cutoff
+    });
+
+    controller.sliding = sliding;
+
+    if let Some((_, toi)) = ground_cast.filter(|_| sliding) {
+        // Momentum is redirected along the surface, so only speed into it makes for a hard landing
+        if controller.ground_tick == 0 {
+            events.send(FpsControllerEvent::Landed {
+                entity,
+                impact_speed: f32::max(-Vec3::dot(velocity.linvel, toi.normal1), 0.0),
+            });
+        }
+
+        slide_movement(
+            wish_direction,
+            wish_speed,
+            toi.normal1,
+            controller,
+            velocity,
+            dt,
+        );
 
-    if let Some((_, toi)) = ground_cast {
+        controller.ground_tick = controller.ground_tick.saturating_add(1);
+    } else if let Some((_, toi)) = ground_cast {
         let has_traction = Vec3::dot(toi.normal1, Vec3::Y) > controller.traction_normal_cutoff;
 
+        if controller.ground_tick == 0 {
+            events.send(FpsControllerEvent::Landed {
+                entity,
+                impact_speed: f32::max(-velocity.linvel.y, 0.0),
+            });
+        }
+
         // Only apply friction after at least one tick, allows b-hopping without losing speed
         if controller.ground_tick >= 1 && has_traction {
             let lateral_speed = velocity.linvel.xz().length();
@@ -186,6 +344,21 @@ fn ground_movement(
 
             if input.jump {
                 velocity.linvel.y = controller.jump_speed;
+
+                // The ground is still in reach for a few ticks after jumping
+                if !controller.jumping {
+                    controller.jumping = true;
+                    events.send(FpsControllerEvent::Jumped { entity });
+                }
+            }
+
+            if controller.ground_tick >= 1 && controller.footstep_distance > f32::EPSILON {
+                controller.stride_progress += velocity.linvel.xz().length() * dt;
+
+                if controller.stride_progress >= controller.footstep_distance {
+                    controller.stride_progress %= controller.footstep_distance;
+                    events.send(FpsControllerEvent::Footstep { entity });
+                }
             }
         }
 
@@ -193,6 +366,7 @@ fn ground_movement(
         controller.ground_tick = controller.ground_tick.saturating_add(1);
     } else {
         controller.ground_tick = 0;
+        controller.jumping = false;
         wish_speed = f32::min(wish_speed, controller.air_speed_cap);
 
         let mut add = acceleration(
@@ -215,23 +389,37 @@ fn ground_movement(
 
     /* Crouching */
 
-    let crouch_height = controller.crouch_height;
-    let upright_height = controller.upright_height;
+    crouch_movement(
+        physics_context.as_ref(),
+        entity,
+        input,
+        controller,
+        collider,
+        transform,
+        dt,
+    );
 
-    let crouch_speed = if input.crouch {
-        -controller.crouch_speed
+    // Crouching starts immediately, but standing up only finishes once there is room to stand
+    let crouched = if input.crouch {
+        true
+    } else if controller.height >= controller.upright_height - f32::EPSILON {
+        false
     } else {
-        controller.uncrouch_speed
+        controller.crouched
     };
-    controller.height += dt * crouch_speed;
-    controller.height = controller.height.clamp(crouch_height, upright_height);
 
-    if let Some(mut capsule) = collider.as_capsule_mut() {
-        capsule.set_segment(Vec3::Y * 0.5, Vec3::Y * controller.height);
+    if crouched != controller.crouched {
+        controller.crouched = crouched;
+
+        events.send(if crouched {
+            FpsControllerEvent::Crouched { entity }
+        } else {
+            FpsControllerEvent::Uncrouched { entity }
+        });
     }
 
     // Step offset
-    if controller.step_offset > f32::EPSILON && controller.ground_tick >= 1 {
+    if controller.step_offset > f32::EPSILON && controller.ground_tick >= 1 && !controller.sliding {
         let cast_offset = velocity.linvel.normalize_or_zero() * controller.radius * 1.0625;
         let cast = physics_context.cast_ray_and_get_normal(
             transform.translation + cast_offset + Vec3::Y * controller.step_offset * 1.0625,
@@ -246,8 +434,8 @@ fn ground_movement(
         }
     }
 
-    // Prevent falling off ledges
-    if controller.ground_tick >= 1 && input.crouch {
+    // Prevent falling off ledges, while sliding the player is already heading off one
+    if controller.ground_tick >= 1 && input.crouch && !controller.sliding {
         for _ in 0..2 {
             // Find the component of our velocity that is overhanging and subtract it off
             let overhang = overhang_component(
@@ -276,6 +464,146 @@ fn ground_movement(
     }
 }
 
+/// Subsystem responsible for sliding and surfing along surfaces too steep to have traction.
+///
+/// Momentum which would be lost to the surface is instead redirected along it, gravity pulls
+/// the player downhill, and the player can steer with air-strafing style acceleration.
+fn slide_movement(
+    wish_direction: Vec3,
+    wish_speed: f32,
+    normal: Vec3,
+    controller: &FpsController,
+    velocity: &mut Velocity,
+    dt: f32,
+) {
+    // Redirect any velocity heading into the surface along it instead
+    let speed = velocity.linvel.length();
+    let into_surface = Vec3::dot(velocity.linvel, normal);
+    if into_surface < 0.0 {
+        let redirected = velocity.linvel - into_surface * normal;
+        let redirected_speed = f32::max(
+            redirected.length(),
+            speed * controller.slide_momentum_retention,
+        );
+        velocity.linvel = redirected.normalize_or_zero() * redirected_speed;
+    }
+
+    // Steering is constrained to the plane of the surface
+    let wish_direction =
+        (wish_direction - Vec3::dot(wish_direction, normal) * normal).normalize_or_zero();
+    velocity.linvel += acceleration(
+        wish_direction,
+        f32::min(wish_speed, controller.slide_speed_cap),
+        controller.slide_acceleration,
+        velocity.linvel,
+        dt,
+    );
+
+    // Only the downhill component of gravity applies, the surface supports the rest
+    let downhill = -Vec3::Y - Vec3::dot(-Vec3::Y, normal) * normal;
+    velocity.linvel += downhill * controller.gravity * dt;
+
+    let slide_speed = velocity.linvel.length();
+    if slide_speed > f32::EPSILON {
+        let drop = slide_speed * controller.slide_friction * dt;
+        let new_speed = f32::max(slide_speed - drop, 0.0).min(controller.max_slide_speed);
+        velocity.linvel *= new_speed / slide_speed;
+    }
+}
+
+/// Subsystem responsible for crouching and standing back up.
+///
+/// On the ground crouching lowers the head, while in the air it raises the feet instead,
+/// allowing crouch-jumps onto higher ledges. Standing back up only extends the capsule as far
+/// as the surrounding geometry allows.
+fn crouch_movement(
+    physics_context: &RapierContext,
+    entity: Entity,
+    input: &FpsControllerInput,
+    controller: &mut FpsController,
+    collider: &mut Collider,
+    transform: &mut Transform,
+    dt: f32,
+) {
+    let crouch_height = controller.crouch_height;
+    let upright_height = controller.upright_height;
+    let airborne = controller.ground_tick == 0;
+
+    let height = if input.crouch {
+        controller.height - dt * controller.crouch_speed
+    } else {
+        let growth = dt * controller.uncrouch_speed;
+        let growth = f32::min(growth, upright_height - controller.height);
+
+        // Grounded players stand up into the space above them, airborne players extend their legs
+        let direction = if airborne { -Vec3::Y } else { Vec3::Y };
+        controller.height
+            + headroom(
+                physics_context,
+                entity,
+                controller,
+                transform,
+                direction,
+                growth,
+            )
+    };
+    let height = height.clamp(crouch_height, upright_height);
+
+    let change = height - controller.height;
+    controller.height = height;
+
+    if airborne {
+        // Keep the head in place and move the feet instead
+        transform.translation.y -= change;
+    }
+
+    if let Some(mut capsule) = collider.as_capsule_mut() {
+        capsule.set_segment(Vec3::Y * 0.5, Vec3::Y * controller.height);
+    }
+}
+
+/// Finds how far the player's capsule can extend in `direction` before touching geometry,
+/// up to a maximum of `distance`.
+fn headroom(
+    physics_context: &RapierContext,
+    entity: Entity,
+    controller: &FpsController,
+    transform: &Transform,
+    direction: Vec3,
+    distance: f32,
+) -> f32 {
+    if distance <= 0.0 {
+        return distance;
+    }
+
+    // Cast a slightly narrower sphere from the end of the capsule so walls don't count as ceilings
+    let probe_radius = controller.radius * 0.9;
+    let end = if direction.y > 0.0 {
+        Vec3::Y * controller.height
+    } else {
+        Vec3::Y * 0.5
+    };
+    let origin = transform.translation + end + direction * (controller.radius - probe_radius);
+
+    let filter = QueryFilter::default()
+        .exclude_rigid_body(entity)
+        .exclude_sensors();
+
+    let cast = physics_context.cast_shape(
+        origin,
+        Quat::IDENTITY,
+        direction,
+        &Collider::ball(probe_radius),
+        distance,
+        filter,
+    );
+
+    match cast {
+        Some((_, toi)) => toi.toi.clamp(0.0, distance),
+        None => distance,
+    }
+}
+
 fn overhang_component(
     entity: Entity,
     transform: &Transform,
@@ -287,7 +615,9 @@ fn overhang_component(
     // If there is a ledge in front of us we will hit the edge of it
     // We can use the normal of the hit to subtract off the component that is overhanging
     let cast_capsule = Collider::capsule(Vec3::Y * 0.125, -Vec3::Y * 0.125, 0.0);
-    let filter = QueryFilter::default().exclude_rigid_body(entity);
+    let filter = QueryFilter::default()
+        .exclude_rigid_body(entity)
+        .exclude_sensors();
     let future_position = transform.translation + velocity * dt;
 
     let cast = physics_context.cast_shape(
@@ -338,3 +668,503 @@ fn acceleration(
     let acceleration_speed = f32::min(acceleration * wish_speed * dt, add_speed);
     wish_direction * acceleration_speed
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::schedule::ExecutorKind;
+    use bevy_rapier3d::rapier::prelude::{vector, ColliderBuilder};
+
+    use super::*;
+
+    /// Creates a physics context with a wide, flat ceiling whose underside is at `height`.
+    fn context_with_ceiling(height: f32) -> RapierContext {
+        let mut context = RapierContext::default();
+
+        let ceiling = ColliderBuilder::cuboid(10.0, 0.1, 10.0)
+            .translation(vector![0.0, height + 0.1, 0.0])
+            .user_data(Entity::from_raw(1).to_bits() as u128)
+            .build();
+
+        context.colliders.insert(ceiling);
+        context
+            .query_pipeline
+            .update(&context.bodies, &context.colliders);
+
+        context
+    }
+
+    fn crouched_player() -> (FpsController, FpsControllerInput, Collider, Transform) {
+        let controller = FpsController {
+            height: FpsController::default().crouch_height,
+            ground_tick: 1,
+            ..default()
+        };
+
+        let collider = Collider::capsule(Vec3::Y * 0.5, Vec3::Y * controller.height, 0.5);
+
+        (
+            controller,
+            FpsControllerInput::default(),
+            collider,
+            Transform::default(),
+        )
+    }
+
+    fn top_of(controller: &FpsController, transform: &Transform) -> f32 {
+        transform.translation.y + controller.height + controller.radius
+    }
+
+    #[test]
+    fn headroom_is_unlimited_without_a_ceiling() {
+        let context = RapierContext::default();
+        let (controller, _, _, transform) = crouched_player();
+
+        let room = headroom(
+            &context,
+            Entity::from_raw(0),
+            &controller,
+            &transform,
+            Vec3::Y,
+            0.5,
+        );
+
+        assert!((room - 0.5).abs() < 1e-4, "Headroom: {room}");
+    }
+
+    #[test]
+    fn headroom_is_limited_by_a_low_ceiling() {
+        let (controller, _, _, transform) = crouched_player();
+        let ceiling = top_of(&controller, &transform) + 0.2;
+        let context = context_with_ceiling(ceiling);
+
+        let room = headroom(
+            &context,
+            Entity::from_raw(0),
+            &controller,
+            &transform,
+            Vec3::Y,
+            1.0,
+        );
+
+        assert!((room - 0.2).abs() < 1e-3, "Headroom: {room}");
+    }
+
+    #[test]
+    fn cannot_stand_up_under_a_low_ceiling() {
+        let (mut controller, input, mut collider, mut transform) = crouched_player();
+        let ceiling = top_of(&controller, &transform) + 0.1;
+        let context = context_with_ceiling(ceiling);
+
+        for _ in 0..60 {
+            crouch_movement(
+                &context,
+                Entity::from_raw(0),
+                &input,
+                &mut controller,
+                &mut collider,
+                &mut transform,
+                1.0 / 60.0,
+            );
+        }
+
+        assert!(controller.height < controller.upright_height);
+        assert!(
+            top_of(&controller, &transform) <= ceiling + 1e-3,
+            "Top: {}; Ceiling: {ceiling}",
+            top_of(&controller, &transform)
+        );
+    }
+
+    #[test]
+    fn stands_up_fully_once_clear_of_the_ceiling() {
+        let (mut controller, input, mut collider, mut transform) = crouched_player();
+        let context = context_with_ceiling(10.0);
+
+        for _ in 0..60 {
+            crouch_movement(
+                &context,
+                Entity::from_raw(0),
+                &input,
+                &mut controller,
+                &mut collider,
+                &mut transform,
+                1.0 / 60.0,
+            );
+        }
+
+        assert!((controller.height - controller.upright_height).abs() < 1e-4);
+        assert_eq!(transform.translation.y, 0.0);
+    }
+
+    #[test]
+    fn crouching_in_the_air_raises_the_feet() {
+        let (mut controller, mut input, mut collider, mut transform) = crouched_player();
+        let context = RapierContext::default();
+
+        controller.height = controller.upright_height;
+        controller.ground_tick = 0;
+        input.crouch = true;
+
+        let head = top_of(&controller, &transform);
+
+        for _ in 0..60 {
+            crouch_movement(
+                &context,
+                Entity::from_raw(0),
+                &input,
+                &mut controller,
+                &mut collider,
+                &mut transform,
+                1.0 / 60.0,
+            );
+        }
+
+        assert_eq!(controller.height, controller.crouch_height);
+        assert!(transform.translation.y > 0.0);
+        assert!((top_of(&controller, &transform) - head).abs() < 1e-4);
+    }
+
+    /// Entity of the body of water created by `context_with_water`.
+    const WATER: Entity = Entity::from_raw(2);
+
+    /// Creates a physics context with a deep pool of water whose surface is at `surface`.
+    fn context_with_water(surface: f32) -> RapierContext {
+        let mut context = RapierContext::default();
+
+        let water = ColliderBuilder::cuboid(10.0, 5.0, 10.0)
+            .translation(vector![0.0, surface - 5.0, 0.0])
+            .sensor(true)
+            .user_data(WATER.to_bits() as u128)
+            .build();
+
+        context.colliders.insert(water);
+        context
+            .query_pipeline
+            .update(&context.bodies, &context.colliders);
+
+        context
+    }
+
+    fn submersion(context: &RapierContext, surface: f32, controller: &FpsController) -> f32 {
+        let collider = Collider::capsule(Vec3::Y * 0.5, Vec3::Y * controller.height, 0.5);
+        let water_surface = |entity| (entity == WATER).then_some(surface);
+
+        water_submersion(
+            context,
+            water_surface,
+            controller,
+            &collider,
+            &Transform::default(),
+        )
+    }
+
+    #[test]
+    fn players_out_of_water_are_not_submerged() {
+        let (controller, ..) = crouched_player();
+
+        assert_eq!(
+            submersion(&RapierContext::default(), 10.0, &controller),
+            0.0
+        );
+    }
+
+    #[test]
+    fn submersion_is_measured_up_to_the_simulated_eyes() {
+        let (controller, _, collider, _) = crouched_player();
+        let eyes = eye_height(&collider, SIMULATED_EYE_HEIGHT).unwrap();
+        let context = context_with_water(eyes / 2.0);
+
+        let submersion = submersion(&context, eyes / 2.0, &controller);
+
+        assert!((submersion - 0.5).abs() < 1e-4, "Submersion: {submersion}");
+    }
+
+    #[test]
+    fn crouching_lowers_the_eyes_underwater() {
+        let (crouched, ..) = crouched_player();
+        let standing = FpsController {
+            height: crouched.upright_height,
+            ..default()
+        };
+        let context = context_with_water(1.7);
+
+        assert_eq!(submersion(&context, 1.7, &crouched), 1.0);
+        assert!(submersion(&context, 1.7, &standing) < 1.0);
+    }
+
+    #[test]
+    fn submerged_swimmers_float_up_and_leave_the_ground() {
+        let mut controller = FpsController {
+            submersion: 1.0,
+            ground_tick: 3,
+            ..default()
+        };
+        let mut velocity = Velocity::zero();
+
+        for _ in 0..30 {
+            swim_movement(
+                &FpsControllerInput::default(),
+                &mut controller,
+                &mut velocity,
+                1.0 / 60.0,
+            );
+        }
+
+        assert_eq!(controller.ground_tick, 0);
+        assert!(velocity.linvel.y > 0.0, "Velocity: {}", velocity.linvel);
+    }
+
+    #[test]
+    fn swimmers_at_the_surface_sink_back_down() {
+        let mut controller = FpsController {
+            submersion: 0.5,
+            ..default()
+        };
+        let mut velocity = Velocity::zero();
+
+        for _ in 0..30 {
+            swim_movement(
+                &FpsControllerInput::default(),
+                &mut controller,
+                &mut velocity,
+                1.0 / 60.0,
+            );
+        }
+
+        assert!(velocity.linvel.y < 0.0, "Velocity: {}", velocity.linvel);
+    }
+
+    #[test]
+    fn swimmers_move_where_they_look() {
+        // Neutrally buoyant, so any vertical movement comes from looking down
+        let mut controller = FpsController {
+            pitch: -std::f32::consts::FRAC_PI_4,
+            submersion: 1.0,
+            buoyancy: FpsController::default().gravity,
+            ..default()
+        };
+        let input = FpsControllerInput {
+            movement: Vec3::Z,
+            ..default()
+        };
+        let mut velocity = Velocity::zero();
+
+        for _ in 0..30 {
+            swim_movement(&input, &mut controller, &mut velocity, 1.0 / 60.0);
+        }
+
+        let linvel = velocity.linvel;
+        assert!(linvel.z < 0.0 && linvel.y < 0.0, "Velocity: {linvel}");
+        assert!((linvel.z - linvel.y).abs() < 1e-3, "Velocity: {linvel}");
+    }
+
+    const SLIDE_TICK: f32 = 1.0 / 60.0;
+
+    /// Normal of the surface created by `context_with_slope`.
+    fn slope_normal(angle: f32) -> Vec3 {
+        Quat::from_rotation_z(angle) * Vec3::Y
+    }
+
+    /// Creates a physics context with a wide plane through the origin, tilted by `angle` radians
+    /// so that it rises towards +X.
+    fn context_with_slope(angle: f32) -> RapierContext {
+        let mut context = RapierContext::default();
+        let centre = slope_normal(angle) * -0.1;
+
+        let slope = ColliderBuilder::cuboid(50.0, 0.1, 50.0)
+            .translation(vector![centre.x, centre.y, centre.z])
+            .rotation(vector![0.0, 0.0, angle])
+            .user_data(Entity::from_raw(1).to_bits() as u128)
+            .build();
+
+        context.colliders.insert(slope);
+        context
+            .query_pipeline
+            .update(&context.bodies, &context.colliders);
+
+        context
+    }
+
+    /// Where an upright player stands just above the plane created by `context_with_slope`,
+    /// near enough for the ground cast to find it.
+    fn standing_on(angle: f32) -> Vec3 {
+        slope_normal(angle) * 0.505 - Vec3::Y * 0.5
+    }
+
+    /// A single player moved a tick at a time by the real movement system, with its velocity
+    /// integrated in place of the physics step.
+    struct Sliding {
+        world: World,
+        schedule: Schedule,
+        player: Entity,
+    }
+
+    impl Sliding {
+        fn new(angle: f32, ground_tick: u8, linvel: Vec3) -> Self {
+            let mut world = World::new();
+
+            let mut time = Time::default();
+            time.update_with_instant(time.startup());
+            world.insert_resource(time);
+            world.insert_resource(context_with_slope(angle));
+            world.init_resource::<Events<FpsControllerEvent>>();
+
+            let controller = FpsController {
+                slide_enabled: true,
+                ground_tick,
+                ..default()
+            };
+            let collider = Collider::capsule(Vec3::Y * 0.5, Vec3::Y * controller.height, 0.5);
+
+            let player = world
+                .spawn((
+                    FpsControllerInput::default(),
+                    MoveMode::Ground,
+                    controller,
+                    collider,
+                    Transform::from_translation(standing_on(angle)),
+                    Velocity {
+                        linvel,
+                        ..default()
+                    },
+                ))
+                .id();
+
+            let mut schedule = Schedule::new();
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            schedule.add_system(map_input_movement);
+
+            Self {
+                world,
+                schedule,
+                player,
+            }
+        }
+
+        fn tick(&mut self) {
+            let mut time = self.world.resource_mut::<Time>();
+            let now = time.last_update().unwrap() + Duration::from_secs_f32(SLIDE_TICK);
+            time.update_with_instant(now);
+
+            self.schedule.run(&mut self.world);
+
+            let mut player = self.world.entity_mut(self.player);
+            let linvel = player.get::<Velocity>().unwrap().linvel;
+            player.get_mut::<Transform>().unwrap().translation += linvel * SLIDE_TICK;
+        }
+
+        /// Swaps the ground for a plane at `angle`, keeping the player over the same spot.
+        fn move_onto(&mut self, angle: f32) {
+            self.world.insert_resource(context_with_slope(angle));
+
+            let mut transform = self.world.get_mut::<Transform>(self.player).unwrap();
+            let over = transform.translation * Vec3::new(1.0, 0.0, 1.0);
+            transform.translation = over + standing_on(angle);
+        }
+
+        fn controller(&self) -> &FpsController {
+            self.world.get::<FpsController>(self.player).unwrap()
+        }
+
+        fn linvel(&self) -> Vec3 {
+            self.world.get::<Velocity>(self.player).unwrap().linvel
+        }
+
+        /// Impact speeds of every landing so far.
+        fn landings(&self) -> Vec<f32> {
+            let events = self.world.resource::<Events<FpsControllerEvent>>();
+
+            events
+                .get_reader()
+                .iter(events)
+                .filter_map(|event| match event {
+                    FpsControllerEvent::Landed { impact_speed, .. } => Some(*impact_speed),
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
+    const STEEP: f32 = std::f32::consts::FRAC_PI_3;
+
+    #[test]
+    fn slides_down_slopes_too_steep_for_traction() {
+        let mut sliding = Sliding::new(STEEP, 1, Vec3::ZERO);
+
+        for _ in 0..30 {
+            sliding.tick();
+        }
+
+        let linvel = sliding.linvel();
+        assert!(sliding.controller().sliding);
+        assert!(sliding.controller().ground_tick > 30);
+        assert!(sliding.landings().is_empty());
+        // Downhill is towards -X, and the player stays on the surface
+        assert!(
+            linvel.x < 0.0 && linvel.length() > 5.0,
+            "Velocity: {linvel}"
+        );
+        assert!(
+            linvel.dot(slope_normal(STEEP)).abs() < 1e-3,
+            "Velocity: {linvel}"
+        );
+    }
+
+    #[test]
+    fn slopes_are_not_slid_down_unless_enabled() {
+        let mut sliding = Sliding::new(STEEP, 1, Vec3::ZERO);
+        sliding
+            .world
+            .get_mut::<FpsController>(sliding.player)
+            .unwrap()
+            .slide_enabled = false;
+
+        sliding.tick();
+
+        assert!(!sliding.controller().sliding);
+    }
+
+    #[test]
+    fn landing_on_a_slope_keeps_momentum() {
+        let mut sliding = Sliding::new(STEEP, 0, Vec3::NEG_Y * 10.0);
+
+        sliding.tick();
+
+        let linvel = sliding.linvel();
+        let landings = sliding.landings();
+        assert!(sliding.controller().sliding);
+        // Only the speed into the surface counts towards the landing
+        assert_eq!(landings.len(), 1);
+        assert!(
+            (landings[0] - 10.0 * STEEP.cos()).abs() < 1e-3,
+            "Landings: {landings:?}"
+        );
+        assert!(linvel.length() >= 10.0 - 1e-3, "Velocity: {linvel}");
+        assert!(
+            linvel.dot(slope_normal(STEEP)).abs() < 1e-3,
+            "Velocity: {linvel}"
+        );
+    }
+
+    #[test]
+    fn sliding_onto_flat_ground_stays_grounded() {
+        let mut sliding = Sliding::new(STEEP, 1, Vec3::ZERO);
+
+        for _ in 0..10 {
+            sliding.tick();
+        }
+
+        let ground_tick = sliding.controller().ground_tick;
+        sliding.move_onto(0.0);
+        sliding.tick();
+
+        let linvel = sliding.linvel();
+        assert!(!sliding.controller().sliding);
+        assert!(sliding.controller().ground_tick > ground_tick);
+        assert!(sliding.landings().is_empty());
+        assert!(linvel.y.abs() < 1e-3, "Velocity: {linvel}");
+        assert!(linvel.x < 0.0, "Velocity: {linvel}");
+    }
+}
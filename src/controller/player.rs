@@ -2,7 +2,10 @@ use std::{f32::consts::TAU, ops::Range};
 
 use bevy::prelude::*;
 
-#[derive(Component)]
+/// Tuning and state of a player's movement.
+///
+/// Rolled back with the simulation, as the events the controller publishes depend on its state.
+#[derive(Component, Reflect)]
 pub struct FpsController {
     pub radius: f32,
     pub gravity: f32,
@@ -19,14 +22,34 @@ pub struct FpsController {
     /// which is a value from [-1, 1], is greater than this value, ground movement is applied
     pub traction_normal_cutoff: f32,
     pub friction_speed_cutoff: f32,
+    /// When enabled, surfaces steeper than `traction_normal_cutoff` are slid along,
+    /// preserving momentum, rather than simply falling down them
+    pub slide_enabled: bool,
+    /// Set while sliding along a steep surface, which still counts as being on the ground
+    pub sliding: bool,
+    /// Fraction of speed kept when momentum is redirected along a steep surface
+    pub slide_momentum_retention: f32,
+    pub slide_acceleration: f32,
+    pub slide_speed_cap: f32,
+    pub slide_friction: f32,
+    pub max_slide_speed: f32,
     pub jump_speed: f32,
+    /// Set when a jump starts, cleared once the player leaves the ground or releases jump
+    pub jumping: bool,
+    /// Distance travelled along the ground between footsteps
+    pub footstep_distance: f32,
+    /// Distance travelled along the ground since the last footstep
+    pub stride_progress: f32,
     pub fly_speed: f32,
     pub crouched_speed: f32,
+    /// Maximum ground speed while aiming down the sights
+    pub aim_speed: f32,
     pub crouch_speed: f32,
     pub uncrouch_speed: f32,
     pub height: f32,
     pub upright_height: f32,
     pub crouch_height: f32,
+    pub crouched: bool,
     pub fast_fly_speed: f32,
     pub fly_friction: f32,
     pub pitch: f32,
@@ -35,6 +58,17 @@ pub struct FpsController {
     pub stop_speed: f32,
     pub step_offset: f32,
     pub free_look_yaw_range: Range<f32>,
+    pub swim_speed: f32,
+    pub fast_swim_speed: f32,
+    pub swim_acceleration: f32,
+    pub water_drag: f32,
+    /// Upward acceleration applied when fully submerged, scaled by `submersion`
+    pub buoyancy: f32,
+    /// Once `submersion` exceeds this value the player starts swimming
+    pub swim_submersion: f32,
+    /// How much of the player, from their feet to their eyes, is below the surface of water.
+    /// A value from [0, 1]
+    pub submersion: f32,
 }
 
 impl Default for FpsController {
@@ -52,23 +86,42 @@ impl Default for FpsController {
             air_acceleration: 20.0,
             max_air_speed: 15.0,
             crouched_speed: 5.0,
+            aim_speed: 5.0,
             crouch_speed: 6.0,
             uncrouch_speed: 8.0,
             height: 1.5,
             upright_height: 2.0,
             crouch_height: 1.25,
+            crouched: false,
             acceleration: 10.0,
             friction: 10.0,
             traction_normal_cutoff: 0.7,
             friction_speed_cutoff: 0.1,
+            slide_enabled: false,
+            sliding: false,
+            slide_momentum_retention: 1.0,
+            slide_acceleration: 20.0,
+            slide_speed_cap: 2.0,
+            slide_friction: 0.05,
+            max_slide_speed: 40.0,
             fly_friction: 0.5,
             pitch: 0.0,
             yaw: 0.0,
             ground_tick: 0,
             stop_speed: 1.0,
             jump_speed: 8.5,
+            jumping: false,
+            footstep_distance: 2.0,
+            stride_progress: 0.0,
             step_offset: 0.0,
             free_look_yaw_range: -TAU / 4.0..TAU / 4.0,
+            swim_speed: 4.0,
+            fast_swim_speed: 6.0,
+            swim_acceleration: 8.0,
+            water_drag: 2.0,
+            buoyancy: 30.0,
+            swim_submersion: 0.6,
+            submersion: 0.0,
         }
     }
 }
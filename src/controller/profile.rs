@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+use super::FpsController;
+
+/// Declares `MovementProfile` along with its conversions to and from `FpsController`, so each
+/// tuning parameter only has to be listed once.
+macro_rules! movement_profile {
+    ($($field:ident: $ty:ty,)*) => {
+        /// The tuning parameters of an `FpsController`, without any of its simulation state.
+        /// Every peer must simulate a player with the same profile to remain in sync.
+        #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+        #[serde(default)]
+        pub struct MovementProfile {
+            $(pub $field: $ty,)*
+        }
+
+        impl MovementProfile {
+            /// Replaces the tuning of an existing controller with this profile, leaving its
+            /// state intact.
+            pub fn apply_to(&self, controller: &mut FpsController) {
+                $(controller.$field = self.$field;)*
+            }
+        }
+
+        impl From<&FpsController> for MovementProfile {
+            fn from(controller: &FpsController) -> Self {
+                Self {
+                    $($field: controller.$field,)*
+                }
+            }
+        }
+    };
+}
+
+movement_profile! {
+    gravity: f32,
+    walk_speed: f32,
+    run_speed: f32,
+    forward_speed: f32,
+    side_speed: f32,
+    air_speed_cap: f32,
+    air_acceleration: f32,
+    max_air_speed: f32,
+    acceleration: f32,
+    friction: f32,
+    traction_normal_cutoff: f32,
+    friction_speed_cutoff: f32,
+    slide_enabled: bool,
+    slide_momentum_retention: f32,
+    slide_acceleration: f32,
+    slide_speed_cap: f32,
+    slide_friction: f32,
+    max_slide_speed: f32,
+    jump_speed: f32,
+    footstep_distance: f32,
+    fly_speed: f32,
+    fast_fly_speed: f32,
+    fly_friction: f32,
+    crouched_speed: f32,
+    aim_speed: f32,
+    crouch_speed: f32,
+    uncrouch_speed: f32,
+    upright_height: f32,
+    crouch_height: f32,
+    stop_speed: f32,
+    step_offset: f32,
+    swim_speed: f32,
+    fast_swim_speed: f32,
+    swim_acceleration: f32,
+    water_drag: f32,
+    buoyancy: f32,
+    swim_submersion: f32,
+}
+
+impl Default for MovementProfile {
+    fn default() -> Self {
+        (&FpsController::default()).into()
+    }
+}
+
+impl MovementProfile {
+    /// A heavily equipped soldier, the baseline for all other profiles.
+    pub fn infantry() -> Self {
+        Self::default()
+    }
+
+    /// A lightly equipped scout, quicker on their feet but less steady.
+    pub fn skirmisher() -> Self {
+        Self {
+            walk_speed: 10.0,
+            run_speed: 16.0,
+            crouched_speed: 6.0,
+            jump_speed: 9.5,
+            air_acceleration: 25.0,
+            friction: 8.0,
+            swim_speed: 5.0,
+            fast_swim_speed: 7.5,
+            ..Self::default()
+        }
+    }
+
+    /// Creates a controller tuned with this profile.
+    pub fn controller(&self) -> FpsController {
+        let mut controller = FpsController::default();
+        self.apply_to(&mut controller);
+        controller
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::reflect::Struct;
+
+    use super::*;
+
+    #[test]
+    fn missing_fields_fall_back_to_defaults() {
+        // A profile saved before footsteps and aiming were added
+        let profile: MovementProfile =
+            serde_json::from_str(r#"{"walk_speed":11.0,"jump_speed":7.0}"#).unwrap();
+
+        assert_eq!(profile.walk_speed, 11.0);
+        assert_eq!(profile.jump_speed, 7.0);
+        assert_eq!(
+            profile.footstep_distance,
+            MovementProfile::default().footstep_distance
+        );
+        assert_eq!(profile.aim_speed, MovementProfile::default().aim_speed);
+    }
+
+    #[test]
+    fn every_tuning_parameter_is_part_of_the_profile() {
+        // Simulation state, and the parameters every player shares
+        const NOT_PROFILED: &[&str] = &[
+            "radius",
+            "sliding",
+            "jumping",
+            "stride_progress",
+            "height",
+            "crouched",
+            "pitch",
+            "yaw",
+            "ground_tick",
+            "free_look_yaw_range",
+            "submersion",
+        ];
+
+        let profile = serde_json::to_value(MovementProfile::default()).unwrap();
+        let controller = FpsController::default();
+
+        for index in 0..controller.field_len() {
+            let field = controller.name_at(index).unwrap();
+            assert!(
+                profile.get(field).is_some() != NOT_PROFILED.contains(&field),
+                "`{field}` must either be in `MovementProfile` or listed as not profiled"
+            );
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::path::Path;
+
+use bevy::{
+    asset::{AssetLoader, AssetPath, HandleId, LoadContext, LoadState, LoadedAsset},
+    prelude::{
+        default, AnimationClip, AssetServer, Assets, Commands, Handle, HandleUntyped, Local, Res,
+        Resource, Scene,
+    },
+    reflect::TypeUuid,
+    utils::{BoxedFuture, HashSet},
+};
+use bevy_kira_audio::prelude::{AudioEmitter, AudioSource};
+use serde::Deserialize;
+
+use super::{
+    Ballistics, Bayonet, FirearmAction, FirearmActions, FirearmBundle, FirearmHandling,
+    FirearmState, Ignition,
+};
+
+/// Folder, relative to the assets root, containing every weapon definition.
+const WEAPON_FOLDER: &str = "weapons";
+
+/// Extension of weapon definition files, which are JSON.
+const WEAPON_EXTENSION: &str = "weapon.json";
+
+/// Id of the weapon given to players who haven't chosen one, or chose one which doesn't exist.
+pub const DEFAULT_WEAPON: &str = "musket";
+
+/*
+    Weapons are described by `<id>.weapon.json` files in the `weapons` asset folder. Every file in
+    that folder is loaded when the game starts, so adding a weapon requires no code changes.
+
+    {
+        "name": "Brown Bess",
+        "model": "musket.glb#Scene0",
+        "fire": { "animation": "musket.glb#Animation0", "sound": "gun_shot.ogg", "cooldown": 1.0 },
+        "strike": { "cooldown": 0.6 },
+        "ballistics": { "powder": 8.0, "barrel_thickness": 5.0, "caliber": 19.0 },
+        "ignition": { "misfire_chance": 0.05, "hang_fire_chance": 0.05, "hang_fire_delay": 0.5 },
+        "handling": { "hip_spread": 0.05, "aimed_spread": 0.01 },
+        "bayonet": { "reach": 1.5, "damage": 50.0 }
+    }
+
+    Any omitted action, ballistics, ignition, handling or bayonet values fall back to their defaults.
+*/
+
+/// A weapon loaded from a `.weapon.json` asset, ready to be given to a player.
+#[derive(TypeUuid, Debug)]
+#[uuid = "5d0c3a4e-6f0e-4b8a-9a57-2f7c1b3e8d21"]
+pub struct WeaponDefinition {
+    /// Identifier of the weapon, taken from its file name.
+    pub id: String,
+    pub name: String,
+    pub model: Handle<Scene>,
+    pub fire: ActionDefinition,
+    pub strike: ActionDefinition,
+    pub ballistics: Ballistics,
+    pub ignition: Ignition,
+    pub handling: FirearmHandling,
+    pub bayonet: Bayonet,
+}
+
+/// The animation, sound and cooldown of a firearm action.
+#[derive(Debug, Default)]
+pub struct ActionDefinition {
+    pub animation: Handle<AnimationClip>,
+    pub sound: Handle<AudioSource>,
+    pub cooldown: f32,
+}
+
+impl WeaponDefinition {
+    /// Creates the components required to wield this weapon.
+    pub fn bundle(&self) -> FirearmBundle {
+        FirearmBundle {
+            model: self.model.clone(),
+            actions: FirearmActions {
+                fire: self.fire.action(),
+                strike: self.strike.action(),
+            },
+            ballistics: self.ballistics.clone(),
+            ignition: self.ignition.clone(),
+            handling: self.handling.clone(),
+            bayonet: self.bayonet.clone(),
+            audio_emitter: AudioEmitter { instances: vec![] },
+            state: FirearmState {
+                powder: self.ballistics.powder,
+                ..default()
+            },
+        }
+    }
+}
+
+impl ActionDefinition {
+    fn action(&self) -> FirearmAction {
+        FirearmAction {
+            animation: self.animation.clone(),
+            sound: self.sound.clone(),
+            cooldown: self.cooldown,
+        }
+    }
+
+    fn resolve(file: &ActionFile, load_context: &LoadContext) -> Self {
+        Self {
+            animation: file
+                .animation
+                .as_deref()
+                .map_or_else(default, |path| load_context.get_handle(path)),
+            sound: file
+                .sound
+                .as_deref()
+                .map_or_else(default, |path| load_context.get_handle(path)),
+            cooldown: file.cooldown,
+        }
+    }
+}
+
+/// The contents of a weapon definition file, before asset paths are resolved into handles.
+#[derive(Deserialize)]
+struct WeaponFile {
+    name: String,
+    model: String,
+    #[serde(default)]
+    fire: ActionFile,
+    #[serde(default)]
+    strike: ActionFile,
+    #[serde(default)]
+    ballistics: Ballistics,
+    #[serde(default)]
+    ignition: Ignition,
+    #[serde(default)]
+    handling: FirearmHandling,
+    #[serde(default)]
+    bayonet: Bayonet,
+}
+
+#[derive(Deserialize, Default)]
+struct ActionFile {
+    animation: Option<String>,
+    sound: Option<String>,
+    #[serde(default)]
+    cooldown: f32,
+}
+
+#[derive(Default)]
+pub struct WeaponDefinitionLoader;
+
+impl AssetLoader for WeaponDefinitionLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let file: WeaponFile = serde_json::from_slice(bytes)?;
+            let id = weapon_id(load_context.path());
+
+            let mut dependencies = vec![AssetPath::from(file.model.as_str()).to_owned()];
+            dependencies.extend(
+                [&file.fire, &file.strike]
+                    .into_iter()
+                    .flat_map(|action| [&action.animation, &action.sound])
+                    .flatten()
+                    .map(|path| AssetPath::from(path.as_str()).to_owned()),
+            );
+
+            let definition = WeaponDefinition {
+                id,
+                name: file.name,
+                model: load_context.get_handle(file.model.as_str()),
+                fire: ActionDefinition::resolve(&file.fire, load_context),
+                strike: ActionDefinition::resolve(&file.strike, load_context),
+                ballistics: file.ballistics,
+                ignition: file.ignition,
+                handling: file.handling,
+                bayonet: file.bayonet,
+            };
+
+            load_context
+                .set_default_asset(LoadedAsset::new(definition).with_dependencies(dependencies));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[WEAPON_EXTENSION]
+    }
+}
+
+/// The id of a weapon is its file name without the extension, e.g. `musket.weapon.json` is `musket`.
+fn weapon_id(path: &Path) -> String {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+
+    file_name
+        .strip_suffix(WEAPON_EXTENSION)
+        .and_then(|name| name.strip_suffix('.'))
+        .unwrap_or(file_name)
+        .to_owned()
+}
+
+/// Keeps every weapon definition loaded for the lifetime of the game.
+#[derive(Resource)]
+pub struct WeaponLibrary {
+    handles: Vec<HandleUntyped>,
+}
+
+impl WeaponLibrary {
+    /// Checks if every weapon definition has finished loading, or failed to.
+    /// Definitions which failed to load are left out, and reported by `report_failed_weapons`.
+    pub fn is_loaded(&self, asset_server: &AssetServer) -> bool {
+        self.handles.iter().all(|handle| {
+            matches!(
+                asset_server.get_load_state(handle.id()),
+                LoadState::Loaded | LoadState::Failed
+            )
+        })
+    }
+
+    /// Lists every loaded weapon definition, ordered by id.
+    pub fn all<'a>(&self, definitions: &'a Assets<WeaponDefinition>) -> Vec<&'a WeaponDefinition> {
+        let mut weapons = self
+            .handles
+            .iter()
+            .filter_map(|handle| definitions.get(&handle.typed_weak()))
+            .collect::<Vec<_>>();
+
+        weapons.sort_by(|a, b| a.id.cmp(&b.id));
+        weapons
+    }
+
+    /// Finds a loaded weapon definition by its id.
+    pub fn get<'a>(
+        &self,
+        definitions: &'a Assets<WeaponDefinition>,
+        id: &str,
+    ) -> Option<&'a WeaponDefinition> {
+        self.handles
+            .iter()
+            .filter_map(|handle| definitions.get(&handle.typed_weak()))
+            .find(|definition| definition.id == id)
+    }
+}
+
+/// System responsible for loading every weapon definition.
+pub fn load_weapon_library(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = asset_server
+        .load_folder(WEAPON_FOLDER)
+        .unwrap_or_else(|error| {
+            log::error!("Unable to load weapon definitions: {error}");
+            vec![]
+        });
+
+    commands.insert_resource(WeaponLibrary { handles });
+}
+
+/// System responsible for reporting every weapon definition which failed to load, once each.
+pub fn report_failed_weapons(
+    weapon_library: Res<WeaponLibrary>,
+    asset_server: Res<AssetServer>,
+    mut reported: Local<HashSet<HandleId>>,
+) {
+    for handle in weapon_library.handles.iter() {
+        if asset_server.get_load_state(handle.id()) != LoadState::Failed {
+            continue;
+        }
+
+        if !reported.insert(handle.id()) {
+            continue;
+        }
+
+        let path = asset_server.get_handle_path(handle.id()).map_or_else(
+            || "unknown".to_owned(),
+            |path| path.path().display().to_string(),
+        );
+
+        log::error!("Unable to load weapon definition '{path}', it will not be available");
+    }
+}
@@ -2,92 +2,498 @@ use std::time::Duration;
 
 use bevy::{
     prelude::{
-        AnimationClip, AnimationPlayer, Bundle, Children, Component, Entity, EventReader,
-        EventWriter, Handle, HierarchyQueryExt, Plugin, Query, Res, Scene, With, Without,
+        AnimationClip, AnimationPlayer, Bundle, Children, Component, Entity, EulerRot, EventReader,
+        EventWriter, Handle, HierarchyQueryExt, Parent, Plugin, Quat, Query, Res, ResMut, Scene,
+        Vec3, With, Without,
     },
     reflect::Reflect,
-    time::Time,
 };
 use bevy_kira_audio::prelude::{Audio, AudioControl, AudioEmitter, AudioSource};
+use bevy_rapier3d::prelude::Velocity;
+
+use serde::Deserialize;
+
+use crate::{
+    config::UserAction, controller::FpsControllerInput, non_linear_time::ExactTime,
+    random::RollbackRng,
+};
+
+pub use definition::*;
+
+mod definition;
 
 pub struct FirearmPlugin;
 
 impl Plugin for FirearmPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.add_event::<FirearmEvent<Fire>>()
+            .add_event::<FirearmEvent<Reload>>()
             .add_event::<FirearmEvent<Fired>>()
+            .add_event::<FirearmEvent<Misfired>>()
+            .add_event::<FirearmEvent<HangFired>>()
+            .add_event::<FirearmEvent<Burst>>()
+            .add_event::<FirearmEvent<Strike>>()
+            .add_event::<FirearmEvent<Struck>>()
             .add_systems((
+                process_firearm_reload_requests,
                 process_firearm_fire_requests,
+                process_firearm_strike_requests,
                 play_fire_soundeffects,
                 play_fire_animation,
+                play_strike_animation,
+                apply_recoil,
             ));
     }
 }
 
 pub struct Fire;
 
+/// Requests a step of loading the firearm, named by the progress it makes.
+pub struct Reload {
+    pub step: LoadingProgress,
+}
+
 pub struct Fired;
 
+/// The priming flashed in the pan without igniting the main charge, so no shot was fired.
+pub struct Misfired;
+
+/// The main charge is smouldering and will fire after a delay.
+pub struct HangFired {
+    /// Seconds until the shot is fired.
+    pub delay: f32,
+}
+
+/// The powder charge was too large for the barrel, which burst instead of firing a shot.
+pub struct Burst;
+
+/// Requests a melee attack with the firearm's bayonet.
+pub struct Strike;
+
+/// A melee attack was made with the firearm's bayonet.
+pub struct Struck;
+
 pub struct FirearmEvent<EventType> {
     pub details: EventType,
     pub entity: Entity,
 }
 
+/// The animation, sound and cooldown of an action. Default handles mean the action has none.
 pub struct FirearmAction {
     pub animation: Handle<AnimationClip>,
     pub sound: Handle<AudioSource>,
     pub cooldown: f32,
 }
 
+/// How far through loading its next shot a firearm is. Each step must follow the one before.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LoadingProgress {
+    /// Nothing is in the barrel.
+    Empty,
+    /// Powder has been poured down the barrel.
+    Poured,
+    /// Wadding and a ball have been inserted on top of the powder.
+    Loaded,
+    /// The charge has been rammed home, ready to fire.
+    #[default]
+    Rammed,
+}
+
+impl LoadingProgress {
+    /// The step which follows this one, or `None` once the firearm is ready to fire.
+    pub fn next(self) -> Option<Self> {
+        match self {
+            Self::Empty => Some(Self::Poured),
+            Self::Poured => Some(Self::Loaded),
+            Self::Loaded => Some(Self::Rammed),
+            Self::Rammed => None,
+        }
+    }
+
+    /// The action which takes a firearm to this step, or `None` for `Empty` as it isn't a step.
+    pub fn action(self) -> Option<UserAction> {
+        match self {
+            Self::Empty => None,
+            Self::Poured => Some(UserAction::Pour),
+            Self::Loaded => Some(UserAction::Load),
+            Self::Rammed => Some(UserAction::Ram),
+        }
+    }
+}
+
 #[derive(Component, Reflect)]
 pub struct FirearmState {
     pub last_fired_seconds: f32,
+    pub last_struck_seconds: f32,
+    /// Powder loaded for the next shot, in grams.
+    pub powder: f32,
+    /// Firearms start out loaded, and are emptied by each shot.
+    pub loading: LoadingProgress,
+    /// When a hang-fire will finally fire, or infinity if nothing is smouldering.
+    pub hang_fire_seconds: f32,
+    /// A burst barrel can never fire again.
+    pub burst: bool,
 }
 
 impl Default for FirearmState {
     fn default() -> Self {
         Self {
-            last_fired_seconds: f32::NEG_INFINITY
+            last_fired_seconds: f32::NEG_INFINITY,
+            last_struck_seconds: f32::NEG_INFINITY,
+            powder: 8.0,
+            loading: LoadingProgress::default(),
+            hang_fire_seconds: f32::INFINITY,
+            burst: false,
         }
     }
 }
 
+impl FirearmState {
+    /// Seconds until `fire` can be performed again, or zero if it can be performed now.
+    pub fn fire_cooldown(&self, fire: &FirearmAction, current_time: f32) -> f32 {
+        f32::max(self.last_fired_seconds + fire.cooldown - current_time, 0.0)
+    }
+
+    /// Seconds until `strike` can be performed again, or zero if it can be performed now.
+    pub fn strike_cooldown(&self, strike: &FirearmAction, current_time: f32) -> f32 {
+        f32::max(
+            self.last_struck_seconds + strike.cooldown - current_time,
+            0.0,
+        )
+    }
+}
+
 #[derive(Component)]
 pub struct FirearmActions {
     pub fire: FirearmAction,
+    pub strike: FirearmAction,
+}
+
+/// The blade fixed to the muzzle of a firearm for melee attacks.
+#[derive(Component, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Bayonet {
+    /// How far in front of the eyes a strike can hit, in metres.
+    pub reach: f32,
+    /// Radius of the shape swept forward by a strike, in metres.
+    pub radius: f32,
+    pub damage: f32,
+    /// Speed imparted on whatever is struck, in metres per second.
+    pub knockback: f32,
+}
+
+impl Default for Bayonet {
+    fn default() -> Self {
+        Self {
+            reach: 1.5,
+            radius: 0.2,
+            damage: 50.0,
+            knockback: 6.0,
+        }
+    }
+}
+
+/// How a firearm is charged and how much it can safely take.
+#[derive(Component, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Ballistics {
+    /// Powder poured for each shot, in grams.
+    pub powder: f32,
+    /// Thickness of the barrel wall, in millimetres.
+    pub barrel_thickness: f32,
+    /// Diameter of the round ball fired, in millimetres.
+    pub caliber: f32,
+    /// Fraction of its speed the ball loses per metre travelled.
+    pub drag: f32,
+    /// Seconds a ball can fly before it is discarded.
+    pub lifetime: f32,
+}
+
+impl Default for Ballistics {
+    fn default() -> Self {
+        Self {
+            powder: FirearmState::default().powder,
+            barrel_thickness: 5.0,
+            caliber: 19.0,
+            drag: 0.005,
+            lifetime: 3.0,
+        }
+    }
+}
+
+impl Ballistics {
+    /// Powder the barrel can contain without risk of bursting, in grams per millimetre of wall.
+    const SAFE_POWDER_PER_THICKNESS: f32 = 2.0;
+
+    /// Muzzle speed for each square root gram of powder, as energy is proportional to the charge.
+    const SPEED_PER_ROOT_POWDER: f32 = 150.0;
+
+    /// Speed the ball leaves the muzzle with when fired with `powder`, in metres per second.
+    pub fn muzzle_speed(&self, powder: f32) -> f32 {
+        Self::SPEED_PER_ROOT_POWDER * powder.max(0.0).sqrt()
+    }
+
+    /// Largest powder charge which can never burst the barrel, in grams.
+    pub fn safe_powder(&self) -> f32 {
+        self.barrel_thickness * Self::SAFE_POWDER_PER_THICKNESS
+    }
+
+    /// Probability that firing with `powder` bursts the barrel.
+    /// Rises from zero at the safe charge, to certain at double the safe charge.
+    pub fn burst_chance(&self, powder: f32) -> f32 {
+        let safe_powder = self.safe_powder();
+        ((powder - safe_powder) / safe_powder).clamp(0.0, 1.0)
+    }
+}
+
+/// How reliably a firearm's flintlock ignites its charge.
+#[derive(Component, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Ignition {
+    /// Probability the priming flashes in the pan without firing.
+    pub misfire_chance: f32,
+    /// Probability the charge smoulders before firing.
+    pub hang_fire_chance: f32,
+    /// Longest a hang-fire can smoulder for, in seconds.
+    pub hang_fire_delay: f32,
+}
+
+impl Default for Ignition {
+    fn default() -> Self {
+        Self {
+            misfire_chance: 0.05,
+            hang_fire_chance: 0.05,
+            hang_fire_delay: 0.5,
+        }
+    }
+}
+
+/// How accurately a firearm can be pointed.
+#[derive(Component, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FirearmHandling {
+    /// Maximum deflection of a shot in radians when firing from the hip.
+    pub hip_spread: f32,
+    /// Maximum deflection of a shot in radians when aiming down the sights.
+    pub aimed_spread: f32,
+    /// Upwards kick of the shooter's view in radians, per gram of powder.
+    pub recoil_pitch: f32,
+    /// Sideways kick of the shooter's view in radians, per gram of powder.
+    pub recoil_yaw: f32,
+    /// Backwards change in the shooter's velocity in metres per second, per gram of powder.
+    pub recoil_impulse: f32,
+}
+
+impl Default for FirearmHandling {
+    fn default() -> Self {
+        Self {
+            hip_spread: 0.05,
+            aimed_spread: 0.01,
+            recoil_pitch: 0.01,
+            recoil_yaw: 0.002,
+            recoil_impulse: 0.25,
+        }
+    }
+}
+
+impl FirearmHandling {
+    pub fn spread(&self, aiming: bool) -> f32 {
+        if aiming {
+            self.aimed_spread
+        } else {
+            self.hip_spread
+        }
+    }
 }
 
 #[derive(Bundle)]
 pub struct FirearmBundle {
     pub model: Handle<Scene>,
     pub actions: FirearmActions,
+    pub ballistics: Ballistics,
+    pub ignition: Ignition,
+    pub handling: FirearmHandling,
+    pub bayonet: Bayonet,
     pub audio_emitter: AudioEmitter,
     pub state: FirearmState,
 }
 
+/// System responsible for loading firearms a step at a time, once they have recovered from firing.
+pub fn process_firearm_reload_requests(
+    mut reload_events: EventReader<FirearmEvent<Reload>>,
+    mut gun_query: Query<(&FirearmActions, &Ballistics, &mut FirearmState)>,
+    time: Res<ExactTime>,
+) {
+    let current_time = time.elapsed_seconds();
+
+    for reload_event in reload_events.iter() {
+        let Ok((actions, ballistics, mut state)) = gun_query.get_mut(reload_event.entity) else {
+            continue;
+        };
+
+        if state.burst || state.hang_fire_seconds.is_finite() {
+            continue;
+        }
+
+        if state.fire_cooldown(&actions.fire, current_time) > 0.0 {
+            continue;
+        }
+
+        let step = reload_event.details.step;
+
+        if state.loading.next() != Some(step) {
+            continue;
+        }
+
+        if step == LoadingProgress::Poured {
+            state.powder = ballistics.powder;
+        }
+
+        state.loading = step;
+    }
+}
+
 pub fn process_firearm_fire_requests(
     mut fire_events: EventReader<FirearmEvent<Fire>>,
     mut fired_events: EventWriter<FirearmEvent<Fired>>,
-    mut gun_query: Query<(&FirearmActions, &mut FirearmState), With<FirearmActions>>,
-    time: Res<Time>,
+    mut misfired_events: EventWriter<FirearmEvent<Misfired>>,
+    mut hang_fired_events: EventWriter<FirearmEvent<HangFired>>,
+    mut burst_events: EventWriter<FirearmEvent<Burst>>,
+    mut gun_query: Query<(
+        Entity,
+        &FirearmActions,
+        &Ballistics,
+        &Ignition,
+        &mut FirearmState,
+    )>,
+    mut rng: ResMut<RollbackRng>,
+    time: Res<ExactTime>,
 ) {
     let current_time = time.elapsed_seconds();
 
     for fire_event in fire_events.iter() {
-        let Ok((actions, mut last_fired)) = gun_query.get_mut(fire_event.entity) else {
+        let Ok((entity, actions, ballistics, ignition, mut state)) =
+            gun_query.get_mut(fire_event.entity)
+        else {
             continue;
         };
 
+        if state.burst || state.hang_fire_seconds.is_finite() {
+            continue;
+        }
+
+        if state.loading != LoadingProgress::Rammed {
+            continue;
+        }
+
         // Check if the firearm is on cooldown
-        if current_time - last_fired.last_fired_seconds <= actions.fire.cooldown {
+        if current_time - state.last_fired_seconds <= actions.fire.cooldown {
+            continue;
+        }
+
+        state.last_fired_seconds = current_time;
+
+        // Always draw both rolls so the sequence of random numbers doesn't depend on the outcome
+        let ignition_roll = rng.next_f32();
+        let delay_roll = rng.next_f32();
+
+        if ignition_roll < ignition.misfire_chance {
+            misfired_events.send(FirearmEvent {
+                details: Misfired,
+                entity,
+            });
+        } else if ignition_roll < ignition.misfire_chance + ignition.hang_fire_chance {
+            let delay = ignition.hang_fire_delay * (0.5 + 0.5 * delay_roll);
+            state.hang_fire_seconds = current_time + delay;
+
+            hang_fired_events.send(FirearmEvent {
+                details: HangFired { delay },
+                entity,
+            });
+        } else {
+            discharge(
+                entity,
+                &mut state,
+                ballistics,
+                &mut rng,
+                &mut fired_events,
+                &mut burst_events,
+            );
+        }
+    }
+
+    // Smouldering charges fire once their delay has passed
+    for (entity, _, ballistics, _, mut state) in gun_query.iter_mut() {
+        if state.hang_fire_seconds > current_time {
             continue;
         }
 
-        last_fired.last_fired_seconds = current_time;
+        state.hang_fire_seconds = f32::INFINITY;
+        state.last_fired_seconds = current_time;
+
+        discharge(
+            entity,
+            &mut state,
+            ballistics,
+            &mut rng,
+            &mut fired_events,
+            &mut burst_events,
+        );
+    }
+}
+
+/// Ignites the main charge, which either fires a shot or bursts an overcharged barrel.
+fn discharge(
+    entity: Entity,
+    state: &mut FirearmState,
+    ballistics: &Ballistics,
+    rng: &mut RollbackRng,
+    fired_events: &mut EventWriter<FirearmEvent<Fired>>,
+    burst_events: &mut EventWriter<FirearmEvent<Burst>>,
+) {
+    // The powder is still needed for the recoil, so only the loading progress is reset
+    state.loading = LoadingProgress::Empty;
+
+    if rng.chance(ballistics.burst_chance(state.powder)) {
+        state.burst = true;
 
-        fired_events.send(FirearmEvent {
-            details: Fired,
-            entity: fire_event.entity,
+        burst_events.send(FirearmEvent {
+            details: Burst,
+            entity,
+        });
+
+        return;
+    }
+
+    fired_events.send(FirearmEvent {
+        details: Fired,
+        entity,
+    });
+}
+
+pub fn process_firearm_strike_requests(
+    mut strike_events: EventReader<FirearmEvent<Strike>>,
+    mut struck_events: EventWriter<FirearmEvent<Struck>>,
+    mut gun_query: Query<(&FirearmActions, &mut FirearmState), With<Bayonet>>,
+    time: Res<ExactTime>,
+) {
+    let current_time = time.elapsed_seconds();
+
+    for strike_event in strike_events.iter() {
+        let Ok((actions, mut state)) = gun_query.get_mut(strike_event.entity) else {
+            continue;
+        };
+
+        // Check if the bayonet is on cooldown
+        if current_time - state.last_struck_seconds <= actions.strike.cooldown {
+            continue;
+        }
+
+        state.last_struck_seconds = current_time;
+
+        struck_events.send(FirearmEvent {
+            details: Struck,
+            entity: strike_event.entity,
         });
     }
 }
@@ -102,6 +508,10 @@ pub fn play_fire_soundeffects(
             continue;
         };
 
+        if actions.fire.sound == Handle::default() {
+            continue;
+        }
+
         audio_emitter
             .instances
             .push(audio.play(actions.fire.sound.clone_weak()).handle());
@@ -110,29 +520,376 @@ pub fn play_fire_soundeffects(
 
 pub fn play_fire_animation(
     mut fired_events: EventReader<FirearmEvent<Fired>>,
-    mut gun_query: Query<&FirearmActions, With<FirearmActions>>,
+    gun_query: Query<&FirearmActions>,
+    children: Query<&Children>,
+    mut query: Query<&mut AnimationPlayer, Without<FirearmActions>>,
+) {
+    for fired_event in fired_events.iter() {
+        let Ok(actions) = gun_query.get(fired_event.entity) else {
+            continue;
+        };
+
+        play_action_animation(&actions.fire, fired_event.entity, &children, &mut query);
+    }
+}
+
+pub fn play_strike_animation(
+    mut struck_events: EventReader<FirearmEvent<Struck>>,
+    gun_query: Query<&FirearmActions>,
     children: Query<&Children>,
     mut query: Query<&mut AnimationPlayer, Without<FirearmActions>>,
+) {
+    for struck_event in struck_events.iter() {
+        let Ok(actions) = gun_query.get(struck_event.entity) else {
+            continue;
+        };
+
+        play_action_animation(&actions.strike, struck_event.entity, &children, &mut query);
+    }
+}
+
+/// Plays the animation of `action` on the first animated descendant of the firearm `entity`.
+///
+/// Actions without an animation leave whatever is playing untouched.
+fn play_action_animation(
+    action: &FirearmAction,
+    entity: Entity,
+    children: &Query<&Children>,
+    query: &mut Query<&mut AnimationPlayer, Without<FirearmActions>>,
+) {
+    if action.animation == Handle::default() {
+        return;
+    }
+
+    for child in children.iter_descendants(entity) {
+        let Ok(mut player) = query.get_mut(child) else {
+            continue;
+        };
+
+        player
+            .set_speed(2.0)
+            .play_with_transition(action.animation.clone_weak(), Duration::from_millis(50))
+            .set_elapsed(0.0);
+
+        break;
+    }
+}
+
+/// System responsible for kicking the view and body of whoever fired a firearm.
+///
+/// Recoil changes the simulation, so this must run inside the rollback schedule.
+pub fn apply_recoil(
+    mut fired_events: EventReader<FirearmEvent<Fired>>,
+    gun_query: Query<(&FirearmHandling, &FirearmState)>,
+    parents: Query<&Parent>,
+    mut shooters: Query<(&mut FpsControllerInput, &mut Velocity)>,
 ) {
     for fired_event in fired_events.iter() {
-        let Ok(actions) = gun_query.get_mut(fired_event.entity) else {
+        let Ok((handling, state)) = gun_query.get(fired_event.entity) else {
+            continue;
+        };
+
+        let Some(shooter) = parents
+            .iter_ancestors(fired_event.entity)
+            .find(|ancestor| shooters.contains(*ancestor))
+        else {
+            continue;
+        };
+
+        let Ok((mut input, mut velocity)) = shooters.get_mut(shooter) else {
             continue;
         };
 
-        for child in children.iter_descendants(fired_event.entity) {
-            let Ok(mut player) = query.get_mut(child) else {
-                continue;
+        // Push back along the direction the shot left the barrel, before the view is kicked
+        let aim = Quat::from_euler(EulerRot::YXZ, input.yaw, input.pitch, 0.0) * Vec3::NEG_Z;
+        velocity.linvel -= aim * handling.recoil_impulse * state.powder;
+
+        // Orientation limits are enforced when the next input is mapped
+        input.pitch += handling.recoil_pitch * state.powder;
+        input.yaw += handling.recoil_yaw * state.powder;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::{event::Events, schedule::ExecutorKind},
+        prelude::{IntoSystemConfigs, Schedule, World},
+    };
+
+    use super::*;
+
+    /// A firearm fired at a range, with a time step of one tick.
+    struct Range {
+        world: World,
+        schedule: Schedule,
+        firearm: Entity,
+    }
+
+    impl Range {
+        fn new(seed: u64, ignition: Ignition, powder: f32) -> Self {
+            let mut world = World::new();
+            world.insert_resource(ExactTime {
+                tick_rate: 60,
+                tick: 0,
+                seconds: 0,
+            });
+            world.insert_resource(RollbackRng::new(seed));
+            world.init_resource::<Events<FirearmEvent<Fire>>>();
+            world.init_resource::<Events<FirearmEvent<Reload>>>();
+            world.init_resource::<Events<FirearmEvent<Fired>>>();
+            world.init_resource::<Events<FirearmEvent<Misfired>>>();
+            world.init_resource::<Events<FirearmEvent<HangFired>>>();
+            world.init_resource::<Events<FirearmEvent<Burst>>>();
+
+            let action = || FirearmAction {
+                animation: Handle::default(),
+                sound: Handle::default(),
+                cooldown: 0.5,
             };
+            let firearm = world
+                .spawn((
+                    FirearmActions {
+                        fire: action(),
+                        strike: action(),
+                    },
+                    Ballistics::default(),
+                    ignition,
+                    FirearmState {
+                        powder,
+                        ..Default::default()
+                    },
+                ))
+                .id();
 
-            player
-                .set_speed(2.0)
-                .play_with_transition(
-                    actions.fire.animation.clone_weak(),
-                    Duration::from_millis(50),
+            let mut schedule = Schedule::new();
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            schedule.add_systems(
+                (
+                    process_firearm_reload_requests,
+                    process_firearm_fire_requests,
                 )
-                .set_elapsed(0.0);
+                    .chain(),
+            );
+
+            Self {
+                world,
+                schedule,
+                firearm,
+            }
+        }
+
+        fn pull_trigger(&mut self) {
+            self.world.send_event(FirearmEvent {
+                details: Fire,
+                entity: self.firearm,
+            });
+            self.tick();
+        }
+
+        /// Pours, loads and rams the next shot, a step each tick.
+        fn reload(&mut self) {
+            for step in [
+                LoadingProgress::Poured,
+                LoadingProgress::Loaded,
+                LoadingProgress::Rammed,
+            ] {
+                self.reload_step(step);
+            }
+        }
+
+        fn reload_step(&mut self, step: LoadingProgress) {
+            self.world.send_event(FirearmEvent {
+                details: Reload { step },
+                entity: self.firearm,
+            });
+            self.tick();
+        }
+
+        fn tick(&mut self) {
+            self.schedule.run(&mut self.world);
+            self.world.resource_mut::<ExactTime>().tick();
+        }
+
+        fn wait(&mut self, seconds: f32) {
+            let tick_rate = self.world.resource::<ExactTime>().tick_rate;
+
+            for _ in 0..(seconds * tick_rate as f32).ceil() as usize {
+                self.tick();
+            }
+        }
+
+        /// Number of `T` events sent so far.
+        fn count<T: Send + Sync + 'static>(&self) -> usize {
+            let events = self.world.resource::<Events<FirearmEvent<T>>>();
+            events.get_reader().iter(events).count()
+        }
+
+        fn state(&self) -> &FirearmState {
+            self.world.get::<FirearmState>(self.firearm).unwrap()
+        }
+    }
+
+    const SEED: u64 = 0x5eed;
+
+    fn reliable() -> Ignition {
+        Ignition {
+            misfire_chance: 0.0,
+            hang_fire_chance: 0.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn misfires_fire_nothing() {
+        let ignition = Ignition {
+            misfire_chance: 1.0,
+            ..reliable()
+        };
+        let mut range = Range::new(SEED, ignition, Ballistics::default().powder);
+
+        range.pull_trigger();
+        range.wait(1.0);
+
+        assert_eq!(range.count::<Misfired>(), 1);
+        assert_eq!(range.count::<HangFired>(), 0);
+        assert_eq!(range.count::<Fired>(), 0);
+    }
+
+    #[test]
+    fn hang_fires_fire_once_the_charge_has_smouldered() {
+        let ignition = Ignition {
+            hang_fire_chance: 1.0,
+            ..reliable()
+        };
+        let delay = ignition.hang_fire_delay;
+        let mut range = Range::new(SEED, ignition, Ballistics::default().powder);
+
+        range.pull_trigger();
+
+        assert_eq!(range.count::<HangFired>(), 1);
+        assert_eq!(range.count::<Fired>(), 0);
+        assert!(range.state().hang_fire_seconds.is_finite());
+
+        // The trigger does nothing while the charge smoulders
+        range.wait(range.world.resource::<ExactTime>().delta_seconds());
+        range.pull_trigger();
+        range.wait(delay);
+
+        assert_eq!(range.count::<HangFired>(), 1);
+        assert_eq!(range.count::<Fired>(), 1);
+        assert!(range.state().hang_fire_seconds.is_infinite());
+    }
+
+    #[test]
+    fn overcharged_barrels_burst_for_good() {
+        let powder = Ballistics::default().safe_powder() * 2.0;
+        let mut range = Range::new(SEED, reliable(), powder);
 
-            break;
+        range.pull_trigger();
+        range.wait(1.0);
+        range.pull_trigger();
+
+        assert_eq!(range.count::<Burst>(), 1);
+        assert_eq!(range.count::<Fired>(), 0);
+        assert!(range.state().burst);
+    }
+
+    #[test]
+    fn safe_charges_never_burst() {
+        let powder = Ballistics::default().safe_powder();
+        let mut range = Range::new(SEED, reliable(), powder);
+
+        for _ in 0..20 {
+            range.pull_trigger();
+            range.wait(1.0);
+            range.reload();
         }
+
+        assert_eq!(range.count::<Burst>(), 0);
+        assert_eq!(range.count::<Fired>(), 20);
+    }
+
+    #[test]
+    fn the_same_seed_produces_the_same_outcomes() {
+        let ignition = Ignition {
+            misfire_chance: 0.3,
+            hang_fire_chance: 0.3,
+            ..Default::default()
+        };
+        let powder = Ballistics::default().safe_powder();
+
+        let outcomes = |seed| {
+            let mut range = Range::new(seed, ignition.clone(), powder);
+
+            (0..20)
+                .map(|_| {
+                    range.pull_trigger();
+                    range.wait(1.0);
+                    range.reload();
+
+                    (
+                        range.count::<Fired>(),
+                        range.count::<Misfired>(),
+                        range.count::<HangFired>(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let outcomes_a = outcomes(SEED);
+        let outcomes_b = outcomes(SEED);
+
+        assert_eq!(outcomes_a, outcomes_b);
+        // Every kind of outcome is expected from this seed
+        let (fired, misfired, hang_fired) = outcomes_a.last().copied().unwrap();
+        assert!(
+            fired > 0 && misfired > 0 && hang_fired > 0,
+            "{outcomes_a:?}"
+        );
+    }
+
+    #[test]
+    fn firing_empties_the_barrel_until_it_is_reloaded() {
+        let mut range = Range::new(SEED, reliable(), Ballistics::default().powder);
+
+        range.pull_trigger();
+        range.wait(1.0);
+        range.pull_trigger();
+
+        assert_eq!(range.count::<Fired>(), 1);
+        assert_eq!(range.state().loading, LoadingProgress::Empty);
+
+        range.reload();
+        range.pull_trigger();
+
+        assert_eq!(range.count::<Fired>(), 2);
+    }
+
+    #[test]
+    fn loading_steps_must_be_taken_in_order() {
+        let mut range = Range::new(SEED, reliable(), Ballistics::default().powder);
+        range.pull_trigger();
+        range.wait(1.0);
+
+        range.reload_step(LoadingProgress::Rammed);
+        range.reload_step(LoadingProgress::Loaded);
+
+        assert_eq!(range.state().loading, LoadingProgress::Empty);
+
+        range.reload_step(LoadingProgress::Poured);
+        range.reload_step(LoadingProgress::Loaded);
+
+        assert_eq!(range.state().loading, LoadingProgress::Loaded);
+    }
+
+    #[test]
+    fn reloading_waits_for_the_firearm_to_recover() {
+        let mut range = Range::new(SEED, reliable(), Ballistics::default().powder);
+
+        range.pull_trigger();
+        range.reload_step(LoadingProgress::Poured);
+
+        assert_eq!(range.state().loading, LoadingProgress::Empty);
     }
 }
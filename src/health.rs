@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+
+use crate::{
+    controller::FpsControllerEvent,
+    non_linear_time::ExactTime,
+    player::{HitboxRegion, OwningPlayer},
+    team::Teams,
+    water::BreathEvent,
+};
+
+/// Component tracking how much more damage a player can take.
+#[derive(Component, Reflect)]
+pub struct Health {
+    pub current: f32,
+    pub maximum: f32,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            current: 100.0,
+            maximum: 100.0,
+        }
+    }
+}
+
+/// What caused a `DamageEvent`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DamageSource {
+    Fall,
+    Drowning,
+    Melee,
+    BarrelBurst,
+    /// A projectile struck the region of the player's body.
+    Projectile(HitboxRegion),
+}
+
+/// Requests `amount` of damage be dealt to the `Health` of `entity`.
+pub struct DamageEvent {
+    pub entity: Entity,
+    pub amount: f32,
+    pub source: DamageSource,
+    /// Handle of the player responsible for the damage, if any.
+    pub instigator: Option<usize>,
+}
+
+/// Reports damage which was actually dealt, after the friendly-fire policy was applied.
+pub struct Injured {
+    pub entity: Entity,
+    /// Handle of the player who was injured, if a player was injured.
+    pub victim: Option<usize>,
+    pub amount: f32,
+    pub source: DamageSource,
+    pub instigator: Option<usize>,
+    /// The injury took the last of the victim's health.
+    pub killed: bool,
+    /// Frame the injury was simulated on, which is repeated if the frame is re-simulated.
+    pub frame: u32,
+}
+
+/// Landing faster than this speed, in metres per second, causes fall damage.
+const SAFE_FALL_SPEED: f32 = 15.0;
+
+/// Damage dealt for each metre per second of impact speed beyond `SAFE_FALL_SPEED`.
+const FALL_DAMAGE_PER_SPEED: f32 = 10.0;
+
+/// System responsible for turning hard landings into fall damage.
+pub fn fall_damage(
+    mut controller_events: EventReader<FpsControllerEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for event in controller_events.iter() {
+        let FpsControllerEvent::Landed {
+            entity,
+            impact_speed,
+        } = event
+        else {
+            continue;
+        };
+
+        if *impact_speed <= SAFE_FALL_SPEED {
+            continue;
+        }
+
+        damage_events.send(DamageEvent {
+            entity: *entity,
+            amount: (impact_speed - SAFE_FALL_SPEED) * FALL_DAMAGE_PER_SPEED,
+            source: DamageSource::Fall,
+            instigator: None,
+        });
+    }
+}
+
+/// Damage dealt for each second a player spends out of breath.
+const DROWNING_DAMAGE_PER_SECOND: f32 = 20.0;
+
+/// System responsible for hurting players who have run out of breath underwater.
+pub fn drowning_damage(
+    time: Res<ExactTime>,
+    mut breath_events: EventReader<BreathEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for event in breath_events.iter() {
+        let BreathEvent::Drowning { entity } = event else {
+            continue;
+        };
+
+        damage_events.send(DamageEvent {
+            entity: *entity,
+            amount: DROWNING_DAMAGE_PER_SECOND * time.delta_seconds(),
+            source: DamageSource::Drowning,
+            instigator: None,
+        });
+    }
+}
+
+/// System responsible for applying all requested damage, according to the friendly-fire policy.
+pub fn apply_damage(
+    mut damage_events: EventReader<DamageEvent>,
+    mut injured_events: EventWriter<Injured>,
+    time: Res<ExactTime>,
+    teams: Res<Teams>,
+    mut query: Query<(&mut Health, Option<&OwningPlayer>)>,
+) {
+    for damage in damage_events.iter() {
+        let Ok((mut health, owner)) = query.get_mut(damage.entity) else {
+            continue;
+        };
+
+        let multiplier = match (damage.instigator, owner) {
+            (Some(instigator), Some(OwningPlayer(victim))) => {
+                teams.damage_multiplier(instigator, *victim)
+            }
+            _ => 1.0,
+        };
+
+        if multiplier <= 0.0 {
+            continue;
+        }
+
+        let amount = damage.amount * multiplier;
+        let was_alive = health.current > 0.0;
+        health.current = f32::max(health.current - amount, 0.0);
+
+        injured_events.send(Injured {
+            entity: damage.entity,
+            victim: owner.map(|OwningPlayer(victim)| *victim),
+            amount,
+            source: damage.source,
+            instigator: damage.instigator,
+            killed: was_alive && health.current <= 0.0,
+            frame: time.frame(),
+        });
+
+        log::debug!(
+            "{:?} took {} {:?} damage, {} remaining",
+            damage.entity,
+            amount,
+            damage.source,
+            health.current
+        );
+    }
+}
@@ -0,0 +1,794 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    config::{Config, ControlBindings, InputDevices, UserAction},
+    controller::FpsController,
+    firearm::{FirearmActions, FirearmState, LoadingProgress},
+    health::{DamageSource, Health, Injured},
+    input::LocalPlayerHandle,
+    non_linear_time::ExactTime,
+    player::{HitboxRegion, OwningPlayer, RightHand, Torso},
+    stats::MatchStats,
+    team::{GameMode, Teams},
+    AppState,
+};
+
+/*
+    The HUD only reacts to `Injured` events, which are sent from the rollback schedule. A rollback
+    re-simulates frames and sends their events again, so each injury is identified by the frame it
+    happened on and shown at most once.
+*/
+
+/// Seconds a kill stays in the kill feed.
+const KILL_FEED_DURATION: f32 = 6.0;
+
+/// Most kills shown in the kill feed at once.
+const KILL_FEED_LENGTH: usize = 5;
+
+/// Seconds the hit marker is shown after landing a hit.
+const HIT_MARKER_DURATION: f32 = 0.3;
+
+/// Seconds a damage indicator is shown after being injured.
+const DAMAGE_INDICATOR_DURATION: f32 = 1.5;
+
+/// Distance of damage indicators from the centre of the screen, as a percentage of its size.
+const DAMAGE_INDICATOR_DISTANCE: f32 = 15.0;
+
+/// Number of recent injuries remembered to avoid showing re-simulated injuries twice.
+const SEEN_INJURIES: usize = 64;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HudState>()
+            .add_system(setup_hud.in_schedule(OnEnter(AppState::InGame)))
+            .add_systems(
+                (
+                    record_injuries,
+                    update_health_readout,
+                    update_kill_feed,
+                    update_hit_marker,
+                    update_damage_indicators,
+                    update_firearm_readout,
+                    update_scoreboard,
+                )
+                    .chain()
+                    .in_set(OnUpdate(AppState::InGame)),
+            );
+    }
+}
+
+/// Everything the HUD is currently showing.
+#[derive(Resource, Default)]
+struct HudState {
+    /// Descriptions of recent kills, and the time they stop being shown.
+    kill_feed: VecDeque<(String, f32)>,
+    /// Time the hit marker stops being shown.
+    hit_marker_until: f32,
+    /// Injuries already shown, as the frame they happened on, who was injured, by whom and how.
+    seen: VecDeque<(u32, Entity, Option<usize>, DamageSource)>,
+}
+
+impl HudState {
+    /// Remembers an injury, returning `false` if it has already been shown.
+    fn first_sighting(&mut self, injured: &Injured) -> bool {
+        let key = (
+            injured.frame,
+            injured.entity,
+            injured.instigator,
+            injured.source,
+        );
+
+        if self.seen.contains(&key) {
+            return false;
+        }
+
+        self.seen.push_back(key);
+
+        while self.seen.len() > SEEN_INJURIES {
+            self.seen.pop_front();
+        }
+
+        true
+    }
+}
+
+#[derive(Component)]
+struct HealthReadout;
+
+#[derive(Component)]
+struct KillFeed;
+
+#[derive(Component)]
+struct HitMarker;
+
+/// Points towards whoever injured the local player.
+#[derive(Component)]
+struct DamageIndicator {
+    instigator: usize,
+    /// Time the indicator stops being shown.
+    until: f32,
+}
+
+/// Describes the state of the local player's firearm, and prompts for what they can do next.
+#[derive(Component)]
+struct FirearmReadout;
+
+/// Fills up as the local player's firearm recovers from firing.
+#[derive(Component)]
+struct FirearmCooldownBar;
+
+/// Lists the stats of every player while the scoreboard binding is held.
+#[derive(Component)]
+struct Scoreboard;
+
+#[derive(Component)]
+struct ScoreboardText;
+
+/// Construct the HUD
+fn setup_hud(mut commands: Commands, assets: Res<AssetServer>) {
+    let font = assets.load("fira_mono.ttf");
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Scoreboard,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.7)),
+                ScoreboardText,
+            ));
+        });
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Percent(5.0),
+                    right: Val::Percent(5.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            let style = TextStyle {
+                font: font.clone(),
+                font_size: 20.0,
+                color: Color::WHITE,
+            };
+
+            parent.spawn((
+                TextBundle::from_sections([
+                    TextSection::new("", style.clone()),
+                    TextSection::new("", style),
+                ])
+                .with_text_alignment(TextAlignment::Right),
+                FirearmReadout,
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(160.0), Val::Px(6.0)),
+                        margin: UiRect::top(Val::Px(4.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            background_color: Color::WHITE.into(),
+                            ..default()
+                        },
+                        FirearmCooldownBar,
+                    ));
+                });
+        });
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font.clone(),
+                font_size: 32.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Percent(5.0),
+                left: Val::Percent(5.0),
+                ..default()
+            },
+            ..default()
+        }),
+        HealthReadout,
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font.clone(),
+                font_size: 20.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_text_alignment(TextAlignment::Right)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Percent(5.0),
+                right: Val::Percent(5.0),
+                ..default()
+            },
+            ..default()
+        }),
+        KillFeed,
+    ));
+
+    let mut hit_marker = TextBundle::from_section(
+        "x",
+        TextStyle {
+            font,
+            font_size: 32.0,
+            color: Color::WHITE,
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        position: UiRect {
+            top: Val::Percent(50.0),
+            left: Val::Percent(50.0),
+            ..default()
+        },
+        // Centre the marker on the crosshair
+        margin: UiRect {
+            top: Val::Px(-16.0),
+            left: Val::Px(-8.0),
+            ..default()
+        },
+        ..default()
+    });
+    hit_marker.visibility = Visibility::Hidden;
+
+    commands.spawn((hit_marker, HitMarker));
+}
+
+/// Turns newly simulated injuries into kill feed entries, hit markers and damage indicators.
+fn record_injuries(
+    mut commands: Commands,
+    mut injured_events: EventReader<Injured>,
+    mut hud: ResMut<HudState>,
+    time: Res<Time>,
+    local_player: Res<LocalPlayerHandle>,
+) {
+    let now = time.elapsed_seconds();
+
+    for injured in injured_events.iter() {
+        if !hud.first_sighting(injured) {
+            continue;
+        }
+
+        let local_victim = injured.victim == Some(local_player.0);
+
+        if injured.killed {
+            let entry = describe_kill(injured.victim, injured.instigator, injured.source);
+            hud.kill_feed.push_back((entry, now + KILL_FEED_DURATION));
+
+            while hud.kill_feed.len() > KILL_FEED_LENGTH {
+                hud.kill_feed.pop_front();
+            }
+        }
+
+        if injured.instigator == Some(local_player.0) && !local_victim {
+            hud.hit_marker_until = now + HIT_MARKER_DURATION;
+        }
+
+        let Some(instigator) = injured.instigator else {
+            continue;
+        };
+
+        if local_victim && instigator != local_player.0 {
+            commands.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        size: Size::new(Val::Px(16.0), Val::Px(16.0)),
+                        margin: UiRect::all(Val::Px(-8.0)),
+                        ..default()
+                    },
+                    background_color: Color::RED.into(),
+                    ..default()
+                },
+                DamageIndicator {
+                    instigator,
+                    until: now + DAMAGE_INDICATOR_DURATION,
+                },
+            ));
+        }
+    }
+}
+
+/// Describes a kill for the kill feed, e.g. "Player 1 shot Player 2 in the head".
+fn describe_kill(victim: Option<usize>, killer: Option<usize>, source: DamageSource) -> String {
+    // Players aren't credited with killing themselves
+    let killer = killer.filter(|killer| Some(*killer) != victim);
+    let victim = victim.map_or_else(|| "Something".to_owned(), player_name);
+
+    match (killer.map(player_name), source) {
+        (_, DamageSource::Fall) => format!("{victim} fell to their death"),
+        (_, DamageSource::Drowning) => format!("{victim} drowned"),
+        (_, DamageSource::BarrelBurst) => format!("{victim} was killed by a burst barrel"),
+        (Some(killer), DamageSource::Melee) => format!("{killer} bayoneted {victim}"),
+        (Some(killer), DamageSource::Projectile(region)) => {
+            let region = match region {
+                HitboxRegion::Head => "head",
+                HitboxRegion::Torso => "chest",
+                HitboxRegion::Legs => "legs",
+            };
+
+            format!("{killer} shot {victim} in the {region}")
+        }
+        (None, _) => format!("{victim} died"),
+    }
+}
+
+/// Name shown for a player, counting from 1.
+fn player_name(player_handle: usize) -> String {
+    format!("Player {}", player_handle + 1)
+}
+
+/// Shows the health of the local player.
+fn update_health_readout(
+    local_player: Res<LocalPlayerHandle>,
+    torsos: Query<(&OwningPlayer, &Health), With<Torso>>,
+    mut readouts: Query<&mut Text, With<HealthReadout>>,
+) {
+    let Some((_, health)) = torsos
+        .iter()
+        .find(|(OwningPlayer(player), _)| *player == local_player.0)
+    else {
+        return;
+    };
+
+    let description = format!("Health {:.0}/{:.0}", health.current.ceil(), health.maximum);
+
+    for mut text in readouts.iter_mut() {
+        // Avoid triggering change detection when the text is already correct
+        if text.sections[0].value != description {
+            text.sections[0].value = description.clone();
+        }
+    }
+}
+
+/// Shows recent kills, removing them once they have been shown for long enough.
+fn update_kill_feed(
+    time: Res<Time>,
+    mut hud: ResMut<HudState>,
+    mut feeds: Query<&mut Text, With<KillFeed>>,
+) {
+    let now = time.elapsed_seconds();
+
+    while matches!(hud.kill_feed.front(), Some((_, until)) if *until <= now) {
+        hud.kill_feed.pop_front();
+    }
+
+    let description = hud
+        .kill_feed
+        .iter()
+        .map(|(entry, _)| entry.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    for mut text in feeds.iter_mut() {
+        if text.sections[0].value != description {
+            text.sections[0].value = description.clone();
+        }
+    }
+}
+
+/// Shows the hit marker briefly after the local player lands a hit.
+fn update_hit_marker(
+    time: Res<Time>,
+    hud: Res<HudState>,
+    mut markers: Query<&mut Visibility, With<HitMarker>>,
+) {
+    let visibility = if time.elapsed_seconds() < hud.hit_marker_until {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    for mut marker in markers.iter_mut() {
+        if *marker != visibility {
+            *marker = visibility;
+        }
+    }
+}
+
+/// Places each damage indicator around the crosshair, in the direction of whoever caused it,
+/// fading it out over time.
+fn update_damage_indicators(
+    mut commands: Commands,
+    time: Res<Time>,
+    local_player: Res<LocalPlayerHandle>,
+    torsos: Query<(&OwningPlayer, &Transform, &FpsController), With<Torso>>,
+    mut indicators: Query<(Entity, &DamageIndicator, &mut Style, &mut BackgroundColor)>,
+) {
+    let now = time.elapsed_seconds();
+
+    let find_torso = |handle: usize| {
+        torsos
+            .iter()
+            .find(|(OwningPlayer(player), _, _)| *player == handle)
+    };
+
+    for (entity, indicator, mut style, mut color) in indicators.iter_mut() {
+        let remaining = (indicator.until - now) / DAMAGE_INDICATOR_DURATION;
+
+        let (Some((_, local, controller)), Some((_, instigator, _))) =
+            (find_torso(local_player.0), find_torso(indicator.instigator))
+        else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        if remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        // Direction of the instigator relative to where the local player is facing
+        let direction =
+            Quat::from_rotation_y(-controller.yaw) * (instigator.translation - local.translation);
+        let angle = f32::atan2(direction.x, -direction.z);
+
+        style.position = UiRect {
+            left: Val::Percent(50.0 + DAMAGE_INDICATOR_DISTANCE * angle.sin()),
+            top: Val::Percent(50.0 - DAMAGE_INDICATOR_DISTANCE * angle.cos()),
+            ..default()
+        };
+
+        color.0.set_a(remaining);
+    }
+}
+
+/// Describes the state of a firearm, along with prompts for what can be done with it next.
+fn firearm_readout(
+    state: &FirearmState,
+    actions: &FirearmActions,
+    now: f32,
+    controls: &ControlBindings,
+) -> (String, String) {
+    let prompt =
+        |action: UserAction, verb: &str| format!("[{}] {verb}", controls.binding_for(action));
+
+    let fire_cooldown = state.fire_cooldown(&actions.fire, now);
+    let strike_prompt = if state.strike_cooldown(&actions.strike, now) > 0.0 {
+        String::new()
+    } else {
+        prompt(UserAction::Melee, "Bayonet")
+    };
+
+    let (status, prompts) = if state.burst {
+        ("Barrel burst".to_owned(), vec![strike_prompt])
+    } else if state.hang_fire_seconds.is_finite() {
+        ("Hang-fire, hold your aim".to_owned(), vec![])
+    } else if fire_cooldown > 0.0 {
+        (
+            format!("Recovering {fire_cooldown:.1}s"),
+            vec![strike_prompt],
+        )
+    } else {
+        match state.loading {
+            LoadingProgress::Empty => (
+                "Empty".to_owned(),
+                vec![prompt(UserAction::Pour, "Pour powder"), strike_prompt],
+            ),
+            LoadingProgress::Poured => (
+                format!("Poured {:.1}g of powder", state.powder),
+                vec![
+                    prompt(UserAction::Load, "Insert wadding and ball"),
+                    strike_prompt,
+                ],
+            ),
+            LoadingProgress::Loaded => (
+                "Ball inserted".to_owned(),
+                vec![prompt(UserAction::Ram, "Ram"), strike_prompt],
+            ),
+            LoadingProgress::Rammed => (
+                format!("Charged with {:.1}g of powder", state.powder),
+                vec![
+                    prompt(UserAction::Fire, "Fire"),
+                    prompt(UserAction::Aim, "Aim"),
+                    strike_prompt,
+                ],
+            ),
+        }
+    };
+
+    let prompts = prompts
+        .into_iter()
+        .filter(|prompt| !prompt.is_empty())
+        .collect::<Vec<_>>()
+        .join("  ");
+
+    (status, prompts)
+}
+
+/// Shows the state of the local player's right-hand firearm, with prompts using their bindings.
+fn update_firearm_readout(
+    time: Res<ExactTime>,
+    config: Res<Config>,
+    local_player: Res<LocalPlayerHandle>,
+    firearms: Query<(&OwningPlayer, &FirearmState, &FirearmActions), With<RightHand>>,
+    mut readouts: Query<&mut Text, With<FirearmReadout>>,
+    mut bars: Query<&mut Style, With<FirearmCooldownBar>>,
+) {
+    let Some((_, state, actions)) = firearms
+        .iter()
+        .find(|(OwningPlayer(player), _, _)| *player == local_player.0)
+    else {
+        return;
+    };
+
+    let now = time.elapsed_seconds();
+    let (status, prompts) = firearm_readout(state, actions, now, &config.controls);
+    let sections = [format!("{status}\n"), prompts];
+
+    for mut text in readouts.iter_mut() {
+        for (section, value) in text.sections.iter_mut().zip(sections.iter()) {
+            // Avoid triggering change detection when the text is already correct
+            if section.value != *value {
+                section.value = value.clone();
+            }
+        }
+    }
+
+    let recovered = if state.burst || actions.fire.cooldown <= 0.0 {
+        1.0
+    } else {
+        1.0 - state.fire_cooldown(&actions.fire, now) / actions.fire.cooldown
+    };
+    let width = Val::Percent(100.0 * recovered.clamp(0.0, 1.0));
+
+    for mut style in bars.iter_mut() {
+        if style.size.width != width {
+            style.size.width = width;
+        }
+    }
+}
+
+/// Shows the scoreboard while the local player holds its binding.
+fn update_scoreboard(
+    keyboard: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    config: Res<Config>,
+    stats: Res<MatchStats>,
+    teams: Res<Teams>,
+    mut scoreboards: Query<&mut Visibility, With<Scoreboard>>,
+    mut texts: Query<&mut Text, With<ScoreboardText>>,
+) {
+    let devices = InputDevices {
+        keyboard: &keyboard,
+        mouse: &mouse,
+        gamepads: &gamepads,
+        gamepad_buttons: &gamepad_buttons,
+        gamepad_axes: &gamepad_axes,
+    };
+
+    let held = config
+        .controls
+        .binding_for(UserAction::Scoreboard)
+        .pressed(&devices);
+
+    let visibility = if held {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    for mut scoreboard in scoreboards.iter_mut() {
+        if *scoreboard != visibility {
+            *scoreboard = visibility;
+        }
+    }
+
+    if !held {
+        return;
+    }
+
+    let mut rows = vec![format!(
+        "{:<14} {:>6} {:>6} {:>6} {:>6} {:>6}",
+        "Player", "Kills", "Deaths", "Shots", "Hits", "Acc."
+    )];
+
+    // Most kills first, with fewer deaths breaking ties
+    let mut players = stats.iter().collect::<Vec<_>>();
+    players.sort_by_key(|(_, stats)| (std::cmp::Reverse(stats.kills), stats.deaths));
+
+    for (handle, stats) in players {
+        let name = match (teams.mode, teams.team(handle)) {
+            (GameMode::TeamVsTeam, Some(team)) => format!("{} ({})", player_name(handle), team + 1),
+            _ => player_name(handle),
+        };
+
+        rows.push(format!(
+            "{:<14} {:>6} {:>6} {:>6} {:>6} {:>5.0}%",
+            name,
+            stats.kills,
+            stats.deaths,
+            stats.shots_fired,
+            stats.hits,
+            stats.accuracy() * 100.0
+        ));
+    }
+
+    let description = rows.join("\n");
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != description {
+            text.sections[0].value = description.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn injury(source: DamageSource) -> Injured {
+        Injured {
+            entity: Entity::from_raw(1),
+            victim: Some(0),
+            amount: 10.0,
+            source,
+            instigator: Some(1),
+            killed: false,
+            frame: 42,
+        }
+    }
+
+    #[test]
+    fn resimulated_injuries_are_only_shown_once() {
+        let mut hud = HudState::default();
+
+        assert!(hud.first_sighting(&injury(DamageSource::Melee)));
+        assert!(!hud.first_sighting(&injury(DamageSource::Melee)));
+    }
+
+    #[test]
+    fn different_hits_on_the_same_frame_are_all_shown() {
+        let mut hud = HudState::default();
+
+        assert!(hud.first_sighting(&injury(DamageSource::Melee)));
+        assert!(hud.first_sighting(&injury(DamageSource::BarrelBurst)));
+    }
+
+    fn firearm_actions() -> FirearmActions {
+        let action = || crate::firearm::FirearmAction {
+            animation: Handle::default(),
+            sound: Handle::default(),
+            cooldown: 1.0,
+        };
+
+        FirearmActions {
+            fire: action(),
+            strike: action(),
+        }
+    }
+
+    /// The readout for a firearm which last fired a while ago, at `loading`.
+    fn readout_when(loading: LoadingProgress) -> (String, String) {
+        let state = FirearmState {
+            loading,
+            last_fired_seconds: 0.0,
+            ..default()
+        };
+
+        firearm_readout(
+            &state,
+            &firearm_actions(),
+            10.0,
+            &ControlBindings::default(),
+        )
+    }
+
+    #[test]
+    fn firearm_readout_prompts_for_each_loading_step_with_its_binding() {
+        let controls = ControlBindings::default();
+        let prompt = |action, verb| format!("[{}] {verb}", controls.binding_for(action));
+        let bayonet = prompt(UserAction::Melee, "Bayonet");
+
+        assert_eq!(
+            readout_when(LoadingProgress::Empty),
+            (
+                "Empty".to_owned(),
+                format!("{}  {bayonet}", prompt(UserAction::Pour, "Pour powder"))
+            )
+        );
+        assert_eq!(
+            readout_when(LoadingProgress::Poured).1,
+            format!(
+                "{}  {bayonet}",
+                prompt(UserAction::Load, "Insert wadding and ball")
+            )
+        );
+        assert_eq!(
+            readout_when(LoadingProgress::Loaded),
+            (
+                "Ball inserted".to_owned(),
+                format!("{}  {bayonet}", prompt(UserAction::Ram, "Ram"))
+            )
+        );
+        assert_eq!(
+            readout_when(LoadingProgress::Rammed).1,
+            format!(
+                "{}  {}  {bayonet}",
+                prompt(UserAction::Fire, "Fire"),
+                prompt(UserAction::Aim, "Aim")
+            )
+        );
+    }
+
+    #[test]
+    fn firearm_readout_counts_down_while_recovering() {
+        let state = FirearmState {
+            loading: LoadingProgress::Empty,
+            last_fired_seconds: 10.0,
+            ..default()
+        };
+
+        let (status, prompts) = firearm_readout(
+            &state,
+            &firearm_actions(),
+            10.5,
+            &ControlBindings::default(),
+        );
+
+        let bayonet = ControlBindings::default()
+            .binding_for(UserAction::Melee)
+            .to_string();
+        assert_eq!(status, "Recovering 0.5s");
+        // Loading only resumes once the shot has been recovered from
+        assert_eq!(prompts, format!("[{bayonet}] Bayonet"));
+    }
+}
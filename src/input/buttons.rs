@@ -44,8 +44,10 @@ impl ButtonInput {
         }
     }
 
-    fn index_of(action: UserAction) -> usize {
-        match action {
+    /// Bit used to send the provided `UserAction` to peers, or `None` for actions which only affect
+    /// the local interface, as they have no bearing on the simulation.
+    fn index_of(action: UserAction) -> Option<usize> {
+        let index = match action {
             UserAction::MoveForward => 0,
             UserAction::MoveBackward => 1,
             UserAction::MoveLeft => 2,
@@ -57,19 +59,26 @@ impl ButtonInput {
             UserAction::Pour => 8,
             UserAction::Load => 9,
             UserAction::Fire => 10,
-        }
+            UserAction::Aim => 11,
+            UserAction::Melee => 12,
+            UserAction::Scoreboard => return None,
+        };
+
+        Some(index)
     }
 
     /// Updates the state of the input associated with the provided `UserAction`.
+    /// Actions which only affect the local interface are not stored.
     pub fn set(&mut self, action: UserAction, value: bool) {
-        let index = Self::index_of(action);
-        self.set_by_index(index, value);
+        if let Some(index) = Self::index_of(action) {
+            self.set_by_index(index, value);
+        }
     }
 
     /// Checks if the button associated with the provided `UserAction` is currently pressed.
+    /// Actions which only affect the local interface are never pressed.
     pub fn get(&self, action: UserAction) -> bool {
-        let index = Self::index_of(action);
-        self.get_by_index(index)
+        Self::index_of(action).map_or(false, |index| self.get_by_index(index))
     }
 }
 
@@ -81,7 +90,10 @@ mod tests {
 
     #[test]
     fn all_user_actions_can_be_stored_and_retreived() {
-        for action in all::<UserAction>() {
+        let simulated =
+            all::<UserAction>().filter(|&action| ButtonInput::index_of(action).is_some());
+
+        for action in simulated {
             let mut input = ButtonInput::default();
 
             assert_eq!(
@@ -113,4 +125,14 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn interface_actions_are_not_sent_to_peers() {
+        let mut input = ButtonInput::default();
+
+        input.set(UserAction::Scoreboard, true);
+
+        assert_eq!(input, ButtonInput::default());
+        assert!(!input.get(UserAction::Scoreboard));
+    }
 }
@@ -6,12 +6,18 @@ use ggrs::PlayerHandle;
 use serde::{Deserialize, Serialize};
 
 pub use buttons::*;
+pub use movement::*;
 pub use pointer::*;
 pub use resync::*;
 
-use crate::{config::UserAction, player::OwningPlayer, controller::FpsControllerInput};
+use crate::{
+    config::{InputDevices, UserAction},
+    controller::FpsControllerInput,
+    player::OwningPlayer,
+};
 
 mod buttons;
+mod movement;
 mod pointer;
 mod resync;
 
@@ -19,6 +25,7 @@ mod resync;
 #[derive(Copy, Clone, PartialEq, Eq, Pod, Zeroable, Default, Serialize, Deserialize, Debug)]
 pub struct PlayerInput {
     pub buttons: ButtonInput,
+    pub movement: MovementInput,
     pub pointer: PointerInput,
     pub resync: ResyncInputEncoded,
 }
@@ -30,10 +37,16 @@ pub fn capture_and_encode_user_input(
     handle: In<PlayerHandle>,
     keyboard_input: Res<Input<KeyCode>>,
     mouse_input: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
     mut mouse_events: EventReader<MouseMotion>,
     mut local_player: ResMut<LocalPlayerHandle>,
     config: Res<crate::config::Config>,
-    torsos: Query<(&Transform, &Velocity, &FpsControllerInput, &OwningPlayer), With<crate::player::Torso>>,
+    torsos: Query<
+        (&Transform, &Velocity, &FpsControllerInput, &OwningPlayer),
+        With<crate::player::Torso>,
+    >,
     mut sync_target: Local<u8>,
 ) -> PlayerInput {
     local_player.0 = handle.0;
@@ -50,19 +63,25 @@ pub fn capture_and_encode_user_input(
             0 => {
                 let Vec3 { x, y, z } = transform.translation;
                 ResyncInput::Translation { x, y, z }
-            },
-            1 => {
-                ResyncInput::Rotation { yaw: controller.yaw, pitch: controller.pitch, roll: 0.0 }
+            }
+            1 => ResyncInput::Rotation {
+                yaw: controller.yaw,
+                pitch: controller.pitch,
+                roll: 0.0,
             },
             2 => {
                 let Vec3 { x, y, z } = velocity.linvel;
                 ResyncInput::Velocity { x, y, z }
-            },
+            }
             3 => {
                 let Vec3 { x, y, z } = velocity.angvel;
-                ResyncInput::AngularVelocity { yaw: x, pitch: y, roll: z }
-            },
-            _ => continue
+                ResyncInput::AngularVelocity {
+                    yaw: x,
+                    pitch: y,
+                    roll: z,
+                }
+            }
+            _ => continue,
         };
 
         input.resync = resync.into();
@@ -70,22 +89,68 @@ pub fn capture_and_encode_user_input(
 
     *sync_target = (*sync_target + 1) % 4;
 
-    for action in all::<UserAction>() {
-        let pressed = match config.controls.input_for(action) {
-            crate::config::UserInput::Keyboard(key) => keyboard_input.pressed(*key),
-            crate::config::UserInput::Mouse(button) => mouse_input.pressed(*button),
-        };
+    let devices = InputDevices {
+        keyboard: &keyboard_input,
+        mouse: &mouse_input,
+        gamepads: &gamepads,
+        gamepad_buttons: &gamepad_buttons,
+        gamepad_axes: &gamepad_axes,
+    };
 
-        if pressed {
+    for action in all::<UserAction>() {
+        if config.controls.binding_for(action).pressed(&devices) {
             input.buttons.set(action, true);
         }
     }
 
-    input.pointer = mouse_events
+    let axis = |positive, negative| match (input.buttons.get(positive), input.buttons.get(negative))
+    {
+        (true, false) => 1.0,
+        (false, true) => -1.0,
+        _ => 0.0,
+    };
+
+    let digital_movement = Vec2::new(
+        axis(UserAction::MoveRight, UserAction::MoveLeft),
+        axis(UserAction::MoveForward, UserAction::MoveBackward),
+    );
+
+    let sticks = &config.controls.gamepad;
+    let stick = |x: GamepadAxisType, y: GamepadAxisType| {
+        gamepads
+            .iter()
+            .map(|gamepad| {
+                let value = |axis| {
+                    gamepad_axes
+                        .get(GamepadAxis::new(gamepad, axis))
+                        .unwrap_or(0.0)
+                };
+                sticks.apply(Vec2::new(value(x), value(y)))
+            })
+            .sum::<Vec2>()
+            .clamp_length_max(1.0)
+    };
+
+    let analog_movement = stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY);
+
+    input.movement = (digital_movement + analog_movement)
+        .clamp(Vec2::NEG_ONE, Vec2::ONE)
+        .into();
+
+    // Inputs are captured once per tick, so the stick turns by a tick's worth of rotation
+    let look = stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY)
+        * sticks.look_speed
+        / config.matchmaking.tick_rate() as f32;
+
+    // Pushing the stick up looks up, whereas moving the mouse up gives a negative delta
+    let stick_look = Vec2::new(look.x, -look.y);
+
+    let mouse_look = mouse_events
         .iter()
         .map(|event| event.delta * config.controls.pointer_sensitivity / 40.0)
-        .sum::<Vec2>()
-        .into();
+        .sum::<Vec2>();
+
+    input.pointer = (mouse_look + stick_look).into();
 
     input
 }
@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+
+/// Desired movement along the ground, with `x` to the right and `z` forwards.
+///
+/// Each axis is quantised to a single byte, which is plenty of precision for an analog stick.
+/// Digital movement from keys always round-trips exactly as `-1`, `0` or `1`.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq, Pod, Zeroable, Default, Serialize, Deserialize, Debug)]
+pub struct MovementInput {
+    x: i8,
+    z: i8,
+}
+
+impl MovementInput {
+    const SCALE: f32 = i8::MAX as f32;
+}
+
+impl From<Vec2> for MovementInput {
+    fn from(movement: Vec2) -> Self {
+        let quantise = |value: f32| (value.clamp(-1.0, 1.0) * Self::SCALE).round() as i8;
+
+        Self {
+            x: quantise(movement.x),
+            z: quantise(movement.y),
+        }
+    }
+}
+
+impl Into<Vec2> for MovementInput {
+    fn into(self) -> Vec2 {
+        Vec2 {
+            x: self.x as f32 / Self::SCALE,
+            y: self.z as f32 / Self::SCALE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digital_movement_round_trips_exactly() {
+        for x in [-1.0, 0.0, 1.0] {
+            for z in [-1.0, 0.0, 1.0] {
+                let movement = Vec2::new(x, z);
+                let decoded: Vec2 = MovementInput::from(movement).into();
+
+                assert_eq!(decoded, movement);
+            }
+        }
+    }
+
+    #[test]
+    fn analog_movement_is_clamped_and_quantised() {
+        let decoded: Vec2 = MovementInput::from(Vec2::new(0.5, -3.0)).into();
+
+        assert!((decoded.x - 0.5).abs() < 1.0 / MovementInput::SCALE);
+        assert_eq!(decoded.y, -1.0);
+    }
+}
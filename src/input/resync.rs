@@ -21,13 +21,29 @@ impl Eq for ResyncInputEncoded {}
 /// Any information which could drift out of sync over time can be added here for transmission along with player input.
 /// How to act on this information, and when to send it, is left up to the caller.
 pub enum ResyncInput {
-    Translation { x: f32, y: f32, z: f32 },
+    Translation {
+        x: f32,
+        y: f32,
+        z: f32,
+    },
 
-    Rotation { yaw: f32, pitch: f32, roll: f32 },
+    Rotation {
+        yaw: f32,
+        pitch: f32,
+        roll: f32,
+    },
 
-    Velocity { x: f32, y: f32, z: f32 },
+    Velocity {
+        x: f32,
+        y: f32,
+        z: f32,
+    },
 
-    AngularVelocity { yaw: f32, pitch: f32, roll: f32 },
+    AngularVelocity {
+        yaw: f32,
+        pitch: f32,
+        roll: f32,
+    },
 
     /// Fallback
     BadData,
@@ -0,0 +1,700 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    multiplayer::INPUT_DELAY,
+    non_linear_time::ExactTime,
+    player::{simulated_transform, HitboxRegion, OwningPlayer, Torso},
+};
+
+/*
+    A shooter's input is generated while they look at the result of one frame, and is simulated
+    `INPUT_DELAY` frames after the next. By the time their shot is simulated every target has moved
+    on from where the shooter saw it. To compensate, the hitboxes of every player are recorded each
+    frame, and shots are tested against the hitboxes as they were when the input was generated.
+
+    The history is deliberately not rolled back. Re-simulating a frame records it again, replacing
+    whatever was predicted, and a frame is only ever read after it has been (re-)simulated.
+*/
+
+/// Frames shots are rewound by, which is when the shooter's input was generated. Projectiles are
+/// spawned through commands, so only start moving the frame after the shot is simulated.
+pub const LAG_COMPENSATION_FRAMES: u32 = INPUT_DELAY as u32 + 2;
+
+/// Number of frames of history kept, which must cover the furthest a shot can be rewound.
+const HISTORY_FRAMES: usize = 32;
+
+/// A capsule around a region of a player on a particular frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hitbox {
+    /// The hitbox's own entity.
+    pub entity: Entity,
+    /// The torso of the player the hitbox belongs to, which takes any damage.
+    pub player: Entity,
+    pub owner: usize,
+    pub region: HitboxRegion,
+    /// Start of the capsule's core segment.
+    pub a: Vec3,
+    /// End of the capsule's core segment.
+    pub b: Vec3,
+    pub radius: f32,
+}
+
+impl Hitbox {
+    /// Creates the hitbox of a capsule `collider` with the provided `transform`.
+    pub fn from_collider(
+        entity: Entity,
+        player: Entity,
+        owner: usize,
+        region: HitboxRegion,
+        collider: &Collider,
+        transform: &Transform,
+    ) -> Option<Self> {
+        let capsule = collider.as_capsule()?;
+        let segment = capsule.segment();
+
+        Some(Self {
+            entity,
+            player,
+            owner,
+            region,
+            a: transform.transform_point(segment.a()),
+            b: transform.transform_point(segment.b()),
+            radius: capsule.radius(),
+        })
+    }
+
+    /// Sweeps a sphere of `radius` from `origin` by `displacement`, returning the fraction of the
+    /// displacement travelled before it touches this hitbox.
+    pub fn cast_sphere(&self, origin: Vec3, displacement: Vec3, radius: f32) -> Option<f32> {
+        // A sphere touches the capsule when its centre touches the capsule inflated by its radius
+        let radius = self.radius + radius;
+
+        if self.distance_squared(origin) <= radius * radius {
+            return Some(0.0);
+        }
+
+        let length = displacement.length();
+
+        if length <= f32::EPSILON {
+            return None;
+        }
+
+        let direction = displacement / length;
+        let distance = self.ray_distance(origin, direction, radius)?;
+
+        (0.0..=length)
+            .contains(&distance)
+            .then_some(distance / length)
+    }
+
+    /// Squared distance from `point` to the capsule's core segment.
+    fn distance_squared(&self, point: Vec3) -> f32 {
+        let ab = self.b - self.a;
+        let t = (point - self.a).dot(ab) / ab.length_squared().max(f32::EPSILON);
+        let closest = self.a + ab * t.clamp(0.0, 1.0);
+
+        point.distance_squared(closest)
+    }
+
+    /// Distance along a ray from outside the capsule until it enters it.
+    fn ray_distance(&self, origin: Vec3, direction: Vec3, radius: f32) -> Option<f32> {
+        let ba = self.b - self.a;
+        let oa = origin - self.a;
+
+        let baba = ba.dot(ba);
+        let bard = ba.dot(direction);
+        let baoa = ba.dot(oa);
+        let rdoa = direction.dot(oa);
+        let oaoa = oa.dot(oa);
+
+        // Test the cylinder between the ends of the segment
+        let a = baba - bard * bard;
+        let b = baba * rdoa - baoa * bard;
+        let c = baba * oaoa - baoa * baoa - radius * radius * baba;
+        let h = b * b - a * c;
+
+        if a > f32::EPSILON && h >= 0.0 {
+            let t = (-b - h.sqrt()) / a;
+            let y = baoa + t * bard;
+
+            if t >= 0.0 && y > 0.0 && y < baba {
+                return Some(t);
+            }
+        }
+
+        // Otherwise the ray can only enter through one of the hemispherical caps
+        [self.a, self.b]
+            .into_iter()
+            .filter_map(|centre| {
+                let oc = origin - centre;
+                let b = direction.dot(oc);
+                let c = oc.dot(oc) - radius * radius;
+                let h = b * b - c;
+
+                (h >= 0.0).then(|| -b - h.sqrt())
+            })
+            .filter(|t| *t >= 0.0)
+            .min_by(f32::total_cmp)
+    }
+}
+
+/// Recent hitboxes of every player, indexed by frame.
+#[derive(Resource, Default)]
+pub struct HitboxHistory {
+    frames: VecDeque<(u32, Vec<Hitbox>)>,
+}
+
+impl HitboxHistory {
+    /// Records the hitboxes of `frame`, replacing that frame and any after it if re-simulating.
+    pub fn record(&mut self, frame: u32, hitboxes: Vec<Hitbox>) {
+        while matches!(self.frames.back(), Some((recorded, _)) if *recorded >= frame) {
+            self.frames.pop_back();
+        }
+
+        self.frames.push_back((frame, hitboxes));
+
+        while self.frames.len() > HISTORY_FRAMES {
+            self.frames.pop_front();
+        }
+    }
+
+    /// The hitboxes as of `frame`, or the oldest available if `frame` is no longer recorded.
+    pub fn rewind(&self, frame: u32) -> &[Hitbox] {
+        self.frames
+            .iter()
+            .rev()
+            .find(|(recorded, _)| *recorded <= frame)
+            .or(self.frames.front())
+            .map_or(&[], |(_, hitboxes)| hitboxes.as_slice())
+    }
+
+    /// Sweeps a sphere against the hitboxes as of `frame`, ignoring those of `shooter`.
+    /// Returns the first hitbox touched, and the fraction of the displacement travelled.
+    pub fn cast_sphere(
+        &self,
+        frame: u32,
+        origin: Vec3,
+        displacement: Vec3,
+        radius: f32,
+        shooter: usize,
+    ) -> Option<(Hitbox, f32)> {
+        self.rewind(frame)
+            .iter()
+            .filter(|hitbox| hitbox.owner != shooter)
+            .filter_map(|hitbox| {
+                let toi = hitbox.cast_sphere(origin, displacement, radius)?;
+                Some((*hitbox, toi))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+}
+
+/// System responsible for recording the hitboxes of every player on the current frame.
+pub fn record_hitboxes(
+    time: Res<ExactTime>,
+    mut history: ResMut<HitboxHistory>,
+    hitboxes: Query<(Entity, &HitboxRegion, &OwningPlayer, &Collider)>,
+    torsos: Query<(), With<Torso>>,
+    parents: Query<&Parent>,
+    transforms: Query<&Transform>,
+) {
+    let hitboxes = hitboxes
+        .iter()
+        .filter_map(|(entity, region, OwningPlayer(owner), collider)| {
+            let player = parents
+                .iter_ancestors(entity)
+                .find(|ancestor| torsos.contains(*ancestor))?;
+            let transform = simulated_transform(entity, &parents, &transforms)?;
+
+            Hitbox::from_collider(entity, player, *owner, *region, collider, &transform)
+        })
+        .collect();
+
+    history.record(time.frame(), hitboxes);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use bevy::ecs::schedule::ExecutorKind;
+    use bytemuck::{Pod, Zeroable};
+    use ggrs::{
+        Config, GGRSRequest, Message, NonBlockingSocket, P2PSession, PlayerType, SessionBuilder,
+        SessionState,
+    };
+
+    use crate::{
+        non_linear_time::track_exact_time,
+        projectile::{move_projectiles, Projectile, ProjectileHit},
+    };
+
+    use super::*;
+
+    fn hitbox_at(x: f32) -> Hitbox {
+        Hitbox {
+            entity: Entity::from_raw(1),
+            player: Entity::from_raw(0),
+            owner: 1,
+            region: HitboxRegion::Torso,
+            a: Vec3::new(x, 0.0, 0.0),
+            b: Vec3::new(x, 1.0, 0.0),
+            radius: 0.5,
+        }
+    }
+
+    #[test]
+    fn spheres_hit_the_side_and_caps_of_a_hitbox() {
+        let hitbox = hitbox_at(0.0);
+
+        let side = hitbox.cast_sphere(Vec3::new(0.0, 0.5, -10.0), Vec3::Z * 20.0, 0.0);
+        assert!((side.unwrap() - 9.5 / 20.0).abs() < 1e-4);
+
+        let cap = hitbox.cast_sphere(Vec3::new(0.0, 10.0, 0.0), Vec3::NEG_Y * 20.0, 0.0);
+        assert!((cap.unwrap() - 8.5 / 20.0).abs() < 1e-4);
+
+        let short = hitbox.cast_sphere(Vec3::new(0.0, 0.5, -10.0), Vec3::Z * 5.0, 0.0);
+        assert_eq!(short, None);
+
+        let wide = hitbox.cast_sphere(Vec3::new(0.7, 0.5, -10.0), Vec3::Z * 20.0, 0.25);
+        assert!(wide.is_some());
+    }
+
+    #[test]
+    fn re_simulated_frames_replace_predicted_frames() {
+        let mut history = HitboxHistory::default();
+
+        for frame in 0..10 {
+            history.record(frame, vec![hitbox_at(frame as f32)]);
+        }
+
+        // Roll back to frame 5, and re-simulate with the target somewhere else
+        for frame in 5..10 {
+            history.record(frame, vec![hitbox_at(-(frame as f32))]);
+        }
+
+        assert_eq!(history.rewind(4)[0].a.x, 4.0);
+        assert_eq!(history.rewind(7)[0].a.x, -7.0);
+        assert_eq!(history.rewind(20)[0].a.x, -9.0);
+    }
+
+    #[test]
+    fn history_is_bounded_and_rewinds_to_the_oldest_frame() {
+        let mut history = HitboxHistory::default();
+
+        for frame in 0..(HISTORY_FRAMES as u32 * 2) {
+            history.record(frame, vec![hitbox_at(frame as f32)]);
+        }
+
+        assert_eq!(history.frames.len(), HISTORY_FRAMES);
+        assert_eq!(history.rewind(0)[0].a.x, HISTORY_FRAMES as f32);
+    }
+
+    /*
+        The following runs two peers through real GGRS sessions, connected by an in-memory network
+        with artificial latency. Each peer simulates in its own world with the game's systems for
+        recording hitboxes and moving projectiles. Player 1 walks along the x axis, turning around
+        regularly so the other peer mispredicts and rolls back. Player 0 fires straight down the z
+        axis at wherever they currently see player 1.
+    */
+
+    /// Ticks of network latency between the peers.
+    const LATENCY: usize = 3;
+
+    /// Distance the target moves every frame, enough that an uncompensated shot always misses.
+    const TARGET_SPEED: f32 = 1.0;
+
+    /// Frames the target walks for before turning around.
+    const TURN_FRAMES: u32 = 20;
+
+    struct TestConfig;
+
+    impl Config for TestConfig {
+        type Input = TestInput;
+        type State = Snapshot;
+        type Address = usize;
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, PartialEq, Pod, Zeroable, Default)]
+    struct TestInput {
+        /// Direction the target walks in, used by player 1.
+        walk: f32,
+        /// Position along the x axis to fire at, used by player 0.
+        aim_x: f32,
+        fire: u32,
+    }
+
+    /// Inputs of every player for the frame being simulated.
+    #[derive(Resource, Default)]
+    struct FrameInputs(Vec<TestInput>);
+
+    /// Frames a projectile hit a player on.
+    #[derive(Resource, Default)]
+    struct Hits(Vec<u32>);
+
+    /// Everything rolled back, standing in for the components and resources the game registers.
+    #[derive(Clone)]
+    struct Snapshot {
+        tick: u16,
+        seconds: u32,
+        target_x: f32,
+        /// Translation, velocity and lifetime of each projectile.
+        projectiles: Vec<(Vec3, Vec3, f32)>,
+        hits: Vec<u32>,
+    }
+
+    fn projectile(translation: Vec3, velocity: Vec3, lifetime: f32) -> impl Bundle {
+        (
+            TransformBundle::from_transform(Transform::from_translation(translation)),
+            Projectile {
+                velocity,
+                radius: 0.01,
+                drag: 0.0,
+                lifetime,
+                owner: 0,
+            },
+        )
+    }
+
+    /// Stands in for the controller, moving the target as player 1 asks.
+    fn walk_target(inputs: Res<FrameInputs>, mut torsos: Query<&mut Transform, With<Torso>>) {
+        for mut transform in torsos.iter_mut() {
+            transform.translation.x += inputs.0[1].walk * TARGET_SPEED;
+        }
+    }
+
+    /// Stands in for firing, spawning a projectile through commands as the game does.
+    fn fire_at_target(mut commands: Commands, inputs: Res<FrameInputs>) {
+        let shot = inputs.0[0];
+
+        if shot.fire == 0 {
+            return;
+        }
+
+        let origin = Vec3::new(shot.aim_x, 0.5, -1.0);
+        commands.spawn(projectile(origin, Vec3::Z * 600.0, 0.1));
+    }
+
+    fn count_hits(
+        time: Res<ExactTime>,
+        mut projectile_hits: EventReader<ProjectileHit>,
+        mut hits: ResMut<Hits>,
+    ) {
+        for hit in projectile_hits.iter() {
+            if hit.hitbox.is_some() {
+                hits.0.push(time.frame());
+            }
+        }
+    }
+
+    /// A peer's world, along with its hitbox history which survives rollbacks.
+    struct Peer {
+        world: World,
+        schedule: Schedule,
+        rollbacks: usize,
+    }
+
+    impl Peer {
+        fn new() -> Self {
+            let mut world = World::new();
+
+            world.insert_resource(ExactTime {
+                tick_rate: 60,
+                tick: 0,
+                seconds: 0,
+            });
+            world.insert_resource(RapierConfiguration {
+                gravity: Vec3::ZERO,
+                ..default()
+            });
+            world.init_resource::<RapierContext>();
+            world.init_resource::<HitboxHistory>();
+            world.init_resource::<Events<ProjectileHit>>();
+            world.init_resource::<FrameInputs>();
+            world.init_resource::<Hits>();
+
+            let hitbox = world
+                .spawn((
+                    HitboxRegion::Torso,
+                    OwningPlayer(1),
+                    Collider::capsule(Vec3::ZERO, Vec3::Y, 0.5),
+                    TransformBundle::default(),
+                ))
+                .id();
+
+            world
+                .spawn((Torso, OwningPlayer(1), TransformBundle::default()))
+                .push_children(&[hitbox]);
+
+            // Commands are only applied once the whole schedule has run, as with the game's
+            let mut schedule = Schedule::default();
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            schedule.add_systems(
+                (
+                    track_exact_time,
+                    walk_target,
+                    record_hitboxes,
+                    fire_at_target,
+                    move_projectiles,
+                    count_hits,
+                )
+                    .chain(),
+            );
+
+            Self {
+                world,
+                schedule,
+                rollbacks: 0,
+            }
+        }
+
+        fn frame(&self) -> u32 {
+            self.world.resource::<ExactTime>().frame()
+        }
+
+        fn target_x(&mut self) -> f32 {
+            let mut torsos = self.world.query_filtered::<&Transform, With<Torso>>();
+            torsos.single(&self.world).translation.x
+        }
+
+        fn hits(&self) -> &[u32] {
+            &self.world.resource::<Hits>().0
+        }
+
+        fn save(&mut self) -> Snapshot {
+            let target_x = self.target_x();
+            let projectiles = self
+                .world
+                .query::<(&Transform, &Projectile)>()
+                .iter(&self.world)
+                .map(|(transform, ball)| (transform.translation, ball.velocity, ball.lifetime))
+                .collect();
+            let time = self.world.resource::<ExactTime>();
+
+            Snapshot {
+                tick: time.tick,
+                seconds: time.seconds,
+                target_x,
+                projectiles,
+                hits: self.hits().to_vec(),
+            }
+        }
+
+        fn load(&mut self, snapshot: Snapshot) {
+            {
+                let mut time = self.world.resource_mut::<ExactTime>();
+                time.tick = snapshot.tick;
+                time.seconds = snapshot.seconds;
+            }
+
+            let mut torsos = self.world.query_filtered::<&mut Transform, With<Torso>>();
+            torsos.single_mut(&mut self.world).translation.x = snapshot.target_x;
+
+            let projectiles = self
+                .world
+                .query_filtered::<Entity, With<Projectile>>()
+                .iter(&self.world)
+                .collect::<Vec<_>>();
+
+            for entity in projectiles {
+                self.world.despawn(entity);
+            }
+
+            for (translation, velocity, lifetime) in snapshot.projectiles {
+                self.world
+                    .spawn(projectile(translation, velocity, lifetime));
+            }
+
+            self.world.resource_mut::<Hits>().0 = snapshot.hits;
+            self.rollbacks += 1;
+        }
+
+        fn handle(&mut self, requests: Vec<GGRSRequest<TestConfig>>) {
+            for request in requests {
+                match request {
+                    GGRSRequest::SaveGameState { cell, frame } => {
+                        cell.save(frame, Some(self.save()), None);
+                    }
+                    GGRSRequest::LoadGameState { cell, .. } => {
+                        self.load(cell.load().expect("Saved state must exist"));
+                    }
+                    GGRSRequest::AdvanceFrame { inputs } => {
+                        self.world.resource_mut::<FrameInputs>().0 =
+                            inputs.iter().map(|(input, _)| *input).collect();
+                        self.schedule.run(&mut self.world);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Packets in flight, with the tick they are delivered on.
+    #[derive(Default)]
+    struct Network {
+        tick: usize,
+        in_flight: Vec<(usize, usize, usize, Message)>,
+    }
+
+    struct LatentSocket {
+        address: usize,
+        network: Arc<Mutex<Network>>,
+    }
+
+    impl NonBlockingSocket<usize> for LatentSocket {
+        fn send_to(&mut self, msg: &Message, addr: &usize) {
+            let mut network = self.network.lock().unwrap();
+            let deliver_on = network.tick + LATENCY;
+
+            network
+                .in_flight
+                .push((deliver_on, self.address, *addr, msg.clone()));
+        }
+
+        fn receive_all_messages(&mut self) -> Vec<(usize, Message)> {
+            let mut network = self.network.lock().unwrap();
+            let tick = network.tick;
+            let (delivered, in_flight) =
+                network
+                    .in_flight
+                    .drain(..)
+                    .partition::<Vec<_>, _>(|(deliver_on, _, to, _)| {
+                        *deliver_on <= tick && *to == self.address
+                    });
+
+            network.in_flight = in_flight;
+
+            delivered
+                .into_iter()
+                .map(|(_, from, _, message)| (from, message))
+                .collect()
+        }
+    }
+
+    fn start_session(local: usize, network: &Arc<Mutex<Network>>) -> P2PSession<TestConfig> {
+        let remote = 1 - local;
+
+        SessionBuilder::<TestConfig>::new()
+            .with_num_players(2)
+            .with_input_delay(INPUT_DELAY)
+            .add_player(PlayerType::Local, local)
+            .unwrap()
+            .add_player(PlayerType::Remote(remote), remote)
+            .unwrap()
+            .start_p2p_session(LatentSocket {
+                address: local,
+                network: network.clone(),
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn latent_shots_hit_where_the_shooter_saw_the_target() {
+        let network = Arc::new(Mutex::new(Network::default()));
+        let mut sessions = [start_session(0, &network), start_session(1, &network)];
+        let mut peers = [Peer::new(), Peer::new()];
+
+        // Fire a handful of shots well after the target turns, so the shooter sees it correctly
+        let shots_on = [33, 53, 73];
+        let mut shots_fired = HashMap::new();
+
+        for _ in 0..1000 {
+            network.lock().unwrap().tick += 1;
+
+            for session in sessions.iter_mut() {
+                session.poll_remote_clients();
+            }
+
+            if sessions
+                .iter()
+                .any(|session| !matches!(session.current_state(), SessionState::Running))
+            {
+                continue;
+            }
+
+            for (handle, (session, peer)) in sessions.iter_mut().zip(peers.iter_mut()).enumerate() {
+                let frame = peer.frame();
+                let target_x = peer.target_x();
+                let fire = handle == 0 && shots_on.contains(&frame);
+                let turned = (frame / TURN_FRAMES) % 2 == 1;
+
+                if fire {
+                    shots_fired.insert(frame, target_x);
+                }
+
+                let input = TestInput {
+                    walk: if turned { -1.0 } else { 1.0 },
+                    aim_x: target_x,
+                    fire: fire as u32,
+                };
+
+                session.add_local_input(handle, input).unwrap();
+
+                match session.advance_frame() {
+                    Ok(requests) => peer.handle(requests),
+                    Err(ggrs::GGRSError::PredictionThreshold) => {}
+                    Err(error) => panic!("Unable to advance frame: {error:?}"),
+                }
+            }
+
+            if peers.iter().all(|peer| peer.frame() > 100) {
+                break;
+            }
+        }
+
+        assert_eq!(
+            shots_fired.len(),
+            shots_on.len(),
+            "Every shot must be fired"
+        );
+
+        for peer in peers.iter() {
+            let expected = shots_on
+                .iter()
+                .map(|frame| frame + LAG_COMPENSATION_FRAMES)
+                .collect::<Vec<_>>();
+
+            // Rollbacks must re-register the same hits, never duplicates or misses
+            assert!(
+                peer.rollbacks > 0,
+                "Both peers must mispredict and roll back"
+            );
+            assert_eq!(peer.hits(), expected);
+        }
+    }
+
+    #[test]
+    fn uncompensated_shots_miss_a_moving_target() {
+        let mut history = HitboxHistory::default();
+
+        for frame in 0..10 {
+            history.record(frame, vec![hitbox_at(frame as f32 * TARGET_SPEED)]);
+        }
+
+        // Aimed at where the target was when the input was generated
+        let frame = 9;
+        let origin = Vec3::new(
+            (frame - LAG_COMPENSATION_FRAMES) as f32 * TARGET_SPEED,
+            0.5,
+            -10.0,
+        );
+
+        let present = history.cast_sphere(frame, origin, Vec3::Z * 20.0, 0.01, 0);
+        let rewound = history.cast_sphere(
+            frame - LAG_COMPENSATION_FRAMES,
+            origin,
+            Vec3::Z * 20.0,
+            0.01,
+            0,
+        );
+
+        assert!(present.is_none());
+        assert!(rewound.is_some());
+    }
+}
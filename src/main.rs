@@ -2,14 +2,14 @@ use config::UserAction;
 use ggrs::InputStatus;
 use input::{LocalPlayerHandle, ResyncInput};
 use non_linear_time::{track_exact_time, ExactTime};
-use player::{Head, OwningPlayer};
+use player::{Head, HitboxRegion, OwningPlayer};
 use simple_logger::SimpleLogger;
 
 use bevy::{
     gltf::Gltf,
     gltf::{GltfMesh, GltfNode},
     prelude::*,
-    window::{CursorGrabMode, PresentMode, WindowResolution},
+    window::{CursorGrabMode, PrimaryWindow, WindowResolution},
 };
 
 use bevy_embedded_assets::EmbeddedAssetPlugin;
@@ -19,21 +19,46 @@ use bevy_kira_audio::prelude::*;
 use bevy_rapier3d::prelude::*;
 
 use controller::*;
-use firearm::{FirearmAction, FirearmActions, FirearmBundle, FirearmEvent, Fired, FirearmState};
-use main_menu::MainMenuPlugin;
-use multiplayer::{GGRSConfig, MatchConfiguration};
-use particles::{setup_smoke_particles, setup_sparks_particles, SmokeCloudEffect, SparksEffect, BloodEffect, setup_blood_particles};
+use firearm::{
+    Ballistics, Bayonet, Burst, FirearmEvent, FirearmHandling, FirearmState, Fired, Struck,
+    WeaponDefinition, WeaponDefinitionLoader, WeaponLibrary, DEFAULT_WEAPON,
+};
+use health::{DamageEvent, DamageSource, Health, Injured};
+use hud::HudPlugin;
+use lag_compensation::HitboxHistory;
+use main_menu::{MainMenuPlugin, MenuScreen};
+use multiplayer::{
+    GGRSConfig, Loadout, Loadouts, MatchConfiguration, PendingLoadouts, SessionSettings,
+};
+use particles::{
+    setup_blood_particles, setup_smoke_particles, setup_sparks_particles, BloodEffect,
+    SmokeCloudEffect, SparksEffect,
+};
+use projectile::{Projectile, ProjectileHit};
+use random::RollbackRng;
+use stats::MatchStats;
+use team::Teams;
+use water::{Breath, BreathEvent, WaterVolume};
 
 mod config;
 mod controller;
 mod firearm;
 mod fog;
+mod health;
+mod hud;
 mod input;
+mod lag_compensation;
 mod main_menu;
 mod multiplayer;
 mod non_linear_time;
 mod particles;
 mod player;
+mod projectile;
+mod random;
+mod stats;
+mod team;
+mod viewmodel;
+mod water;
 
 const SPAWN_POINT: Vec3 = Vec3::new(0.0, 1.0, 0.0);
 
@@ -71,14 +96,26 @@ fn main() {
         .register_rollback_component::<GlobalTransform>()
         .register_rollback_component::<Transform>()
         .register_rollback_component::<Velocity>()
+        .register_rollback_component::<FpsController>()
+        .register_rollback_component::<FpsControllerInput>()
         .register_rollback_component::<FirearmState>()
+        .register_rollback_component::<Breath>()
+        .register_rollback_component::<Health>()
+        .register_rollback_component::<Projectile>()
         .register_rollback_resource::<ExactTime>()
+        .register_rollback_resource::<RollbackRng>()
+        .register_rollback_resource::<MatchStats>()
         // these systems will be executed as part of the advance frame update
         .with_rollback_schedule({
             let mut schedule = Schedule::default();
 
+            // Set ordering configured on the app doesn't carry over to the rollback schedule
             schedule
-                .add_systems((activate_spatial_audio_when_applicable, track_exact_time))
+                .configure_set(FpsControllerSet::Input.before(FpsControllerSet::Update))
+                .add_systems((
+                    activate_spatial_audio_when_applicable,
+                    track_exact_time.before(FpsControllerSet::Input),
+                ))
                 .add_system(map_player_input_to_controller_input.in_set(FpsControllerSet::Input))
                 .add_systems(
                     (
@@ -91,30 +128,52 @@ fn main() {
                 )
                 .add_systems(
                     (
-                        ensure_all_players_are_spawned,
-                        resync_externally_owned_entities,
-                        input_handler,
-                        firearm::process_firearm_fire_requests,
-                        firearm::play_fire_soundeffects,
-                        firearm::play_fire_animation,
-                        fog::clear_fog_over_time,
-                        fog::increase_fog_after_shots,
-                        manage_cursor,
-                        scene_colliders,
-                        respawn,
-                        check_for_bullet_collisions,
-                        activate_camera_of_local_player,
+                        // Nested as groups are limited to 15 systems, each group is chained in turn
+                        (
+                            ensure_all_players_are_spawned,
+                            resync_externally_owned_entities,
+                            water::track_breath,
+                            health::drowning_damage,
+                            health::fall_damage,
+                            player::play_movement_soundeffects,
+                            input_handler,
+                            firearm::process_firearm_reload_requests,
+                            firearm::process_firearm_fire_requests,
+                            firearm::process_firearm_strike_requests,
+                            firearm::play_fire_soundeffects,
+                            firearm::play_fire_animation,
+                            firearm::play_strike_animation,
+                        )
+                            .chain(),
+                        (
+                            fog::clear_fog_over_time,
+                            fog::increase_fog_after_shots,
+                            manage_cursor,
+                            scene_colliders,
+                            respawn,
+                            player::fit_hitboxes,
+                            lag_compensation::record_hitboxes,
+                            fire_projectiles,
+                            // Shots leave along the aim from before the kick
+                            firearm::apply_recoil,
+                            projectile::move_projectiles,
+                            projectile::injure_struck_players,
+                            show_projectile_impacts,
+                            check_for_melee_hits,
+                            injure_burst_shooters,
+                        )
+                            .chain(),
+                        (
+                            health::apply_damage,
+                            stats::record_stats,
+                            viewmodel::record_viewmodel_triggers,
+                            activate_camera_of_local_player,
+                        )
+                            .chain(),
                     )
                         .chain()
-                        .in_set(OnUpdate(AppState::InGame)),
-                )
-                .add_systems(
-                    (
-                        player::head_bobbing,
-                        player::right_hand_bobbing,
-                        player::left_hand_bobbing,
-                    )
                         .in_set(OnUpdate(AppState::InGame))
+                        // Gameplay reacts to, and adjusts, the movement simulated this frame
                         .after(FpsControllerSet::Update),
                 );
 
@@ -126,13 +185,32 @@ fn main() {
     // Configure the Rest of the Application
     app.add_state::<AppState>()
         .add_event::<FirearmEvent<firearm::Fire>>()
+        .add_event::<FirearmEvent<firearm::Reload>>()
         .add_event::<FirearmEvent<firearm::Fired>>()
+        .add_event::<FirearmEvent<firearm::Misfired>>()
+        .add_event::<FirearmEvent<firearm::HangFired>>()
+        .add_event::<FirearmEvent<firearm::Burst>>()
+        .add_event::<FirearmEvent<firearm::Strike>>()
+        .add_event::<FirearmEvent<firearm::Struck>>()
+        .add_event::<BreathEvent>()
+        .add_event::<FpsControllerEvent>()
+        .add_event::<DamageEvent>()
+        .add_event::<Injured>()
+        .add_event::<ProjectileHit>()
         .insert_resource(LocalPlayerHandle(0))
+        .init_resource::<Loadout>()
+        .init_resource::<PendingLoadouts>()
+        .init_resource::<multiplayer::ReadyPeers>()
         .insert_resource(ExactTime {
             tick_rate: config.matchmaking.tick_rate().into(),
             tick: 0,
             seconds: 0,
         })
+        .init_resource::<RollbackRng>()
+        .init_resource::<HitboxHistory>()
+        .init_resource::<viewmodel::ViewmodelTriggers>()
+        .init_resource::<Teams>()
+        .init_resource::<MatchStats>()
         .insert_resource(MatchConfiguration {
             room_id: config.matchmaking.room.clone(),
             players: config.matchmaking.players.into(),
@@ -150,11 +228,7 @@ fn main() {
                     primary_window: Some(Window {
                         title: "Muskrats at Dawn".to_owned(),
                         mode: config.graphics.mode,
-                        present_mode: if config.graphics.vsync {
-                            PresentMode::AutoVsync
-                        } else {
-                            PresentMode::AutoNoVsync
-                        },
+                        present_mode: config.graphics.present_mode(),
                         resolution: WindowResolution::new(
                             config.graphics.width as f32,
                             config.graphics.height as f32,
@@ -170,29 +244,40 @@ fn main() {
         .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
         .add_plugin(AudioPlugin)
         .add_plugin(MainMenuPlugin)
+        .add_plugin(HudPlugin)
         .add_plugin(HanabiPlugin)
+        .add_asset::<WeaponDefinition>()
+        .init_asset_loader::<WeaponDefinitionLoader>()
         .configure_set(FpsControllerSet::Input.before(FpsControllerSet::Update))
+        .add_system(player::apply_camera_settings)
+        .add_system(firearm::report_failed_weapons)
+        .add_system(apply_graphics_and_audio_settings)
+        .add_system(viewmodel::animate_viewmodels.in_set(OnUpdate(AppState::InGame)))
+        .add_system(stats::save_match_summary_on_exit.in_base_set(CoreSet::Last))
+        .add_system(stats::save_match_summary_on_match_end.in_schedule(OnExit(AppState::InGame)))
         .add_systems(
             (
-                multiplayer::start_matchbox_socket,
                 setup_sparks_particles,
                 setup_smoke_particles,
                 setup_blood_particles,
+                player::setup_movement_sounds,
+                firearm::load_weapon_library,
             )
                 .on_startup(),
         )
+        // Only connect once the player has chosen where to play
+        .add_system(multiplayer::start_matchbox_socket.in_schedule(OnEnter(MenuScreen::Lobby)))
+        .add_system(multiplayer::leave_lobby.in_schedule(OnExit(MenuScreen::Lobby)))
         .add_systems(
             (
                 multiplayer::watch_for_connected_peers,
                 multiplayer::start_game_when_ready,
             )
-                .in_set(OnUpdate(AppState::MainMenu)),
+                .in_set(OnUpdate(AppState::MainMenu))
+                .in_set(OnUpdate(MenuScreen::Lobby)),
         )
         .add_systems(
-            (
-                load_level,
-                add_audio_listener_to_head_of_local_player,
-            )
+            (load_level, add_audio_listener_to_head_of_local_player)
                 .in_schedule(OnEnter(AppState::InGame)),
         )
         .run();
@@ -201,7 +286,15 @@ fn main() {
 fn resync_externally_owned_entities(
     inputs: Res<PlayerInputs<GGRSConfig>>,
     local_player: Res<LocalPlayerHandle>,
-    mut torsos: Query<(&mut Transform, &mut Velocity, &mut FpsControllerInput, &OwningPlayer), (With<player::Torso>, With<Rollback>)>
+    mut torsos: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &mut FpsControllerInput,
+            &OwningPlayer,
+        ),
+        (With<player::Torso>, With<Rollback>),
+    >,
 ) {
     for (mut transform, mut velocity, mut controller, OwningPlayer(player)) in torsos.iter_mut() {
         if local_player.0 == *player {
@@ -223,10 +316,16 @@ fn resync_externally_owned_entities(
             ResyncInput::Rotation { yaw, pitch, .. } => {
                 controller.yaw = yaw;
                 controller.pitch = pitch;
-            },
+            }
             ResyncInput::Velocity { x, y, z } => velocity.linvel = Vec3 { x, y, z },
-            ResyncInput::AngularVelocity { yaw, pitch, roll } => velocity.angvel = Vec3 { x: yaw, y: pitch, z: roll },
-            ResyncInput::BadData => {},
+            ResyncInput::AngularVelocity { yaw, pitch, roll } => {
+                velocity.angvel = Vec3 {
+                    x: yaw,
+                    y: pitch,
+                    z: roll,
+                }
+            }
+            ResyncInput::BadData => {}
         }
     }
 }
@@ -243,6 +342,39 @@ fn activate_spatial_audio_when_applicable(
     }
 }
 
+/// Applies changes to the graphics and audio settings as soon as they are made.
+fn apply_graphics_and_audio_settings(
+    config: Res<config::Config>,
+    mut msaa: ResMut<Msaa>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    audio: Res<Audio>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+
+    let samples: Msaa = config.graphics.msaa.into();
+
+    if *msaa != samples {
+        *msaa = samples;
+    }
+
+    let present_mode = config.graphics.present_mode();
+
+    for mut window in windows.iter_mut() {
+        // Avoid triggering change detection when the settings are already applied
+        if window.mode != config.graphics.mode {
+            window.mode = config.graphics.mode;
+        }
+
+        if window.present_mode != present_mode {
+            window.present_mode = present_mode;
+        }
+    }
+
+    audio.set_volume(f64::from(config.audio.volume));
+}
+
 fn activate_camera_of_local_player(
     local_player: Res<LocalPlayerHandle>,
     mut query: Query<(&OwningPlayer, &mut Camera)>,
@@ -273,9 +405,14 @@ fn ensure_all_players_are_spawned(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut rip: ResMut<bevy_ggrs::RollbackIdProvider>,
-    assets: Res<AssetServer>,
+    weapon_library: Res<WeaponLibrary>,
+    weapon_definitions: Res<Assets<WeaponDefinition>>,
     inputs: Res<PlayerInputs<GGRSConfig>>,
-    torsos: Query<&OwningPlayer, (With<player::Torso>, With<Rollback>)>
+    session_settings: Res<SessionSettings>,
+    loadouts: Res<Loadouts>,
+    teams: Res<Teams>,
+    config: Res<config::Config>,
+    torsos: Query<&OwningPlayer, (With<player::Torso>, With<Rollback>)>,
 ) {
     for (player_handle, (_input, status)) in inputs.iter().enumerate() {
         if let InputStatus::Disconnected = status {
@@ -288,9 +425,14 @@ fn ensure_all_players_are_spawned(
 
         if !already_spawned {
             // Spawn the player
-            let player_entities = player::spawn_player(&mut commands, player_handle);
-
-            let player_handle = meshes.add(Mesh::from(shape::Capsule {
+            let player_entities = player::spawn_player(
+                &mut commands,
+                player_handle,
+                &session_settings.movement,
+                &config.graphics.camera,
+            );
+
+            let player_mesh_handle = meshes.add(Mesh::from(shape::Capsule {
                 radius: 0.5,
                 rings: 8,
                 depth: 1.0,
@@ -299,7 +441,7 @@ fn ensure_all_players_are_spawned(
                 uv_profile: default(),
             }));
             let player_material_handle = materials.add(StandardMaterial {
-                base_color: Color::rgb(0.3, 0.8, 0.3),
+                base_color: teams.color(player_handle),
                 ..default()
             });
 
@@ -314,23 +456,32 @@ fn ensure_all_players_are_spawned(
 
             commands
                 .entity(player_entities.torso)
-                .insert((player_handle, player_material_handle));
-
-            commands.entity(player_entities.right_hand).insert((
-                FirearmBundle {
-                    model: assets.load("musket.glb#Scene0"),
-                    actions: FirearmActions {
-                        fire: FirearmAction {
-                            animation: assets.load("musket.glb#Animation0"),
-                            sound: assets.load("gun_shot.ogg"),
-                            cooldown: 1.0,
-                        },
-                    },
-                    audio_emitter: AudioEmitter { instances: vec![] },
-                    state: default(),
-                },
-                rip.next(),
-            ));
+                .insert((player_mesh_handle, player_material_handle));
+
+            commands
+                .entity(player_entities.right_hand)
+                .insert(rip.next());
+
+            // The game only starts once every weapon is loaded, so every peer finds the same weapon
+            let weapon_id = loadouts
+                .get(player_handle)
+                .map_or(DEFAULT_WEAPON, |loadout| loadout.weapon.as_str());
+
+            let weapon = weapon_library
+                .get(&weapon_definitions, weapon_id)
+                .or_else(|| {
+                    warn!("Weapon {weapon_id} does not exist, using {DEFAULT_WEAPON} instead");
+                    weapon_library.get(&weapon_definitions, DEFAULT_WEAPON)
+                });
+
+            match weapon {
+                Some(weapon) => {
+                    commands
+                        .entity(player_entities.right_hand)
+                        .insert(weapon.bundle());
+                }
+                None => error!("Weapon {DEFAULT_WEAPON} does not exist"),
+            }
 
             commands.entity(player_entities.feet).insert(rip.next());
             commands
@@ -379,10 +530,16 @@ struct MainScene {
 
 fn input_handler(
     inputs: Res<PlayerInputs<GGRSConfig>>,
-    hands: Query<(Entity, &OwningPlayer), (With<player::RightHand>, With<firearm::FirearmActions>)>,
+    hands: Query<
+        (Entity, &OwningPlayer, &FirearmState),
+        (With<player::RightHand>, With<firearm::FirearmActions>),
+    >,
+    torsos: Query<(&OwningPlayer, &FpsController), With<player::Torso>>,
     mut fire_events: EventWriter<firearm::FirearmEvent<firearm::Fire>>,
+    mut reload_events: EventWriter<firearm::FirearmEvent<firearm::Reload>>,
+    mut strike_events: EventWriter<firearm::FirearmEvent<firearm::Strike>>,
 ) {
-    for (entity, OwningPlayer(player)) in hands.iter() {
+    for (entity, OwningPlayer(player), state) in hands.iter() {
         let Some((input, status)) = inputs.get(*player) else {
             continue;
         };
@@ -391,10 +548,39 @@ fn input_handler(
             continue;
         }
 
+        if input.buttons.get(UserAction::Melee) {
+            strike_events.send(firearm::FirearmEvent {
+                details: firearm::Strike,
+                entity,
+            });
+        }
+
+        // Only the next step of loading is requested, so holding every step loads one per frame
+        if let Some(step) = state.loading.next() {
+            if step
+                .action()
+                .map_or(false, |action| input.buttons.get(action))
+            {
+                reload_events.send(firearm::FirearmEvent {
+                    details: firearm::Reload { step },
+                    entity,
+                });
+            }
+        }
+
         if !input.buttons.get(UserAction::Fire) {
             continue;
         }
 
+        // Powder won't ignite underwater
+        let underwater = torsos.iter().any(|(OwningPlayer(owner), controller)| {
+            owner == player && controller.submersion >= 1.0
+        });
+
+        if underwater {
+            continue;
+        }
+
         fire_events.send(firearm::FirearmEvent {
             details: firearm::Fire,
             entity,
@@ -402,74 +588,201 @@ fn input_handler(
     }
 }
 
-fn check_for_bullet_collisions(
+fn fire_projectiles(
     mut fired_events: EventReader<FirearmEvent<Fired>>,
-    hands: Query<&Parent, With<player::RightHand>>,
-    heads: Query<&GlobalTransform, With<player::Head>>,
-    players: Query<Entity, With<OwningPlayer>>,
-    rapier_context: Res<RapierContext>,
+    mut rng: ResMut<RollbackRng>,
+    mut rip: ResMut<bevy_ggrs::RollbackIdProvider>,
+    hands: Query<
+        (
+            &Parent,
+            &OwningPlayer,
+            &FirearmHandling,
+            &Ballistics,
+            &FirearmState,
+        ),
+        With<player::RightHand>,
+    >,
+    heads: Query<&Parent, With<player::Head>>,
+    torsos: Query<(&Transform, &Collider, &FpsControllerInput), With<player::Torso>>,
     mut commands: Commands,
-    impact_effect: Res<SparksEffect>,
     smoke_effect: Res<SmokeCloudEffect>,
-    blood_effect: Res<BloodEffect>,
 ) {
     for fired_event in fired_events.iter() {
-        let Ok(parent) = hands.get(fired_event.entity) else {
+        let Ok((parent, OwningPlayer(player), handling, ballistics, state)) =
+            hands.get(fired_event.entity)
+        else {
+            continue;
+        };
+
+        let Ok(torso) = heads.get(parent.get()) else {
+            continue;
+        };
+
+        let Ok((torso, collider, input)) = torsos.get(torso.get()) else {
             continue;
         };
 
-        let Ok(head) = heads.get(parent.get()) else {
+        // Fire from the simulated eye position, ignoring any cosmetic head movement
+        let Some(eye_height) = eye_height(collider, SIMULATED_EYE_HEIGHT) else {
             continue;
         };
 
-        let ray_dir = head.forward();
-        let ray_pos = head.translation() + 2.0 * ray_dir;
-        let max_toi = f32::INFINITY;
-        let solid = true;
-        let filter = QueryFilter::new();
+        // Aim along the simulated view, the head's global transform isn't updated mid-tick
+        let aim = Quat::from_euler(EulerRot::YXZ, input.yaw, input.pitch, 0.0) * Vec3::NEG_Z;
+        let direction = rng.cone(aim, handling.spread(input.aim));
+        let origin = torso.translation + Vec3::Y * eye_height;
+        let muzzle = origin + 2.0 * direction;
 
         commands.spawn((
             ParticleEffectBundle {
                 effect: ParticleEffect::new(smoke_effect.effect.clone_weak()),
-                transform: Transform::from_translation(ray_pos).looking_to(ray_dir, Vec3::Y),
+                transform: Transform::from_translation(muzzle).looking_to(direction, Vec3::Y),
                 ..default()
             },
             RigidBody::KinematicVelocityBased,
             Velocity {
-                linvel: head.forward() * 100.,
+                linvel: aim * 100.,
                 ..default()
-            }
+            },
         ));
 
-        let Some((entity, toi)) = rapier_context.cast_ray(
-            ray_pos, ray_dir, max_toi, solid, filter
-        ) else {
-            continue;
-        };
-
-        let hit_point = ray_pos + ray_dir * toi;
-
+        commands.spawn((
+            TransformBundle::from_transform(Transform::from_translation(origin)),
+            Projectile {
+                velocity: direction * ballistics.muzzle_speed(state.powder),
+                radius: ballistics.caliber / 2000.0,
+                drag: ballistics.drag,
+                lifetime: ballistics.lifetime,
+                owner: *player,
+            },
+            rip.next(),
+        ));
+    }
+}
 
-        
+fn show_projectile_impacts(
+    mut hits: EventReader<ProjectileHit>,
+    hitboxes: Query<&GlobalTransform, With<HitboxRegion>>,
+    mut commands: Commands,
+    impact_effect: Res<SparksEffect>,
+    blood_effect: Res<BloodEffect>,
+) {
+    for hit in hits.iter() {
+        if let Some(hitbox) = hit.hitbox {
+            debug!(
+                "Hit {:?} of Player {:?} at point {}",
+                hitbox.region, hit.entity, hit.point
+            );
+
+            // The player was hit where the shooter saw them, so move the blood onto the same
+            // spot of the region where it is now
+            let rewound_centre = (hitbox.a + hitbox.b) / 2.0;
+            let point = hitboxes.get(hitbox.entity).map_or(hit.point, |transform| {
+                transform.translation() + hit.point - rewound_centre
+            });
 
-        if players.get(entity).is_ok() {
-            println!("Hit Player {:?} at point {}", entity, hit_point);
             commands.spawn(ParticleEffectBundle {
                 effect: ParticleEffect::new(blood_effect.effect.clone_weak()),
-                transform: Transform::from_translation(hit_point),
+                transform: Transform::from_translation(point),
                 ..default()
             });
         } else {
-            println!("Hit Entity {:?} at point {}", entity, hit_point);
+            debug!("Hit Entity {:?} at point {}", hit.entity, hit.point);
             commands.spawn(ParticleEffectBundle {
                 effect: ParticleEffect::new(impact_effect.effect.clone_weak()),
-                transform: Transform::from_translation(hit_point),
+                transform: Transform::from_translation(hit.point),
                 ..default()
             });
         }
     }
 }
 
+fn check_for_melee_hits(
+    mut struck_events: EventReader<FirearmEvent<Struck>>,
+    hands: Query<(&Parent, &OwningPlayer, &Bayonet), With<player::RightHand>>,
+    heads: Query<&Parent, With<player::Head>>,
+    torsos: Query<(&Transform, &Collider, &FpsController), With<player::Torso>>,
+    mut velocities: Query<&mut Velocity>,
+    rapier_context: Res<RapierContext>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for struck_event in struck_events.iter() {
+        let Ok((parent, OwningPlayer(striker), bayonet)) = hands.get(struck_event.entity) else {
+            continue;
+        };
+
+        let Ok(torso_entity) = heads.get(parent.get()).map(Parent::get) else {
+            continue;
+        };
+
+        let Ok((torso, collider, controller)) = torsos.get(torso_entity) else {
+            continue;
+        };
+
+        // Strike from the simulated eye position, ignoring any cosmetic head movement
+        let Some(eye_height) = eye_height(collider, SIMULATED_EYE_HEIGHT) else {
+            continue;
+        };
+
+        let direction =
+            Quat::from_euler(EulerRot::YXZ, controller.yaw, controller.pitch, 0.0) * Vec3::NEG_Z;
+        let origin = torso.translation + Vec3::Y * eye_height;
+        let filter = QueryFilter::new()
+            .exclude_sensors()
+            .exclude_rigid_body(torso_entity);
+
+        let Some((entity, _)) = rapier_context.cast_shape(
+            origin,
+            Quat::IDENTITY,
+            direction,
+            &Collider::ball(bayonet.radius),
+            bayonet.reach,
+            filter,
+        ) else {
+            continue;
+        };
+
+        damage_events.send(DamageEvent {
+            entity,
+            amount: bayonet.damage,
+            source: DamageSource::Melee,
+            instigator: Some(*striker),
+        });
+
+        // Struck after the controller has moved everyone this frame, so the knockback is carried
+        // into the physics step in full rather than depending on the system order
+        if let Ok(mut velocity) = velocities.get_mut(entity) {
+            velocity.linvel += direction * bayonet.knockback;
+        }
+    }
+}
+
+fn injure_burst_shooters(
+    mut burst_events: EventReader<FirearmEvent<Burst>>,
+    parents: Query<&Parent>,
+    torsos: Query<(Entity, &OwningPlayer), With<player::Torso>>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    /// Damage dealt to whoever was holding a firearm when its barrel burst.
+    const BURST_DAMAGE: f32 = 60.0;
+
+    for burst_event in burst_events.iter() {
+        let Some((torso, OwningPlayer(shooter))) = parents
+            .iter_ancestors(burst_event.entity)
+            .find_map(|ancestor| torsos.get(ancestor).ok())
+        else {
+            continue;
+        };
+
+        damage_events.send(DamageEvent {
+            entity: torso,
+            amount: BURST_DAMAGE,
+            source: DamageSource::BarrelBurst,
+            instigator: Some(*shooter),
+        });
+    }
+}
+
 fn scene_colliders(
     mut commands: Commands,
     mut main_scene: ResMut<MainScene>,
@@ -495,9 +808,38 @@ fn scene_colliders(
             continue;
         };
 
+        let is_water = node
+            .extras
+            .as_ref()
+            .map(WaterVolume::is_water)
+            .unwrap_or(false);
+
         let gltf_mesh = gltf_mesh_assets.get(&gltf_mesh).unwrap();
         for mesh_primitive in &gltf_mesh.primitives {
             let mesh = mesh_assets.get(&mesh_primitive.mesh).unwrap();
+
+            if is_water {
+                let Some(aabb) = mesh.compute_aabb() else {
+                    continue;
+                };
+
+                let (water, transform) = WaterVolume::from_aabb(&aabb, node.transform);
+
+                commands.spawn((
+                    Collider::cuboid(
+                        aabb.half_extents.x,
+                        aabb.half_extents.y,
+                        aabb.half_extents.z,
+                    ),
+                    Sensor,
+                    RigidBody::Fixed,
+                    water,
+                    TransformBundle::from_transform(transform),
+                ));
+
+                continue;
+            }
+
             commands.spawn((
                 Collider::from_bevy_mesh(mesh, &ComputedColliderShape::TriMesh).unwrap(),
                 RigidBody::Fixed,
@@ -523,4 +865,4 @@ fn manage_cursor(
         window.cursor.grab_mode = CursorGrabMode::None;
         window.cursor.visible = true;
     }
-}
\ No newline at end of file
+}
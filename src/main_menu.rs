@@ -1,31 +1,390 @@
-use bevy::prelude::*;
+use std::{num::NonZeroUsize, ops::RangeInclusive};
+
+use bevy::{app::AppExit, prelude::*, window::WindowMode};
 use bevy_kira_audio::prelude::AudioReceiver;
+use enum_iterator::all;
+
+use crate::{
+    config::{BindingSlot, Config, Msaa, ParticleDetail, UserAction, UserInput},
+    firearm::{WeaponDefinition, WeaponLibrary},
+    multiplayer::{Loadout, MatchConfiguration, PendingLoadouts, SocketResource},
+    team::{FriendlyFire, GameMode},
+    AppState,
+};
+
+/// Most players which can be chosen for a match.
+const MAX_PLAYERS: usize = 8;
 
-use crate::AppState;
+/// Longest room name which can be entered.
+const MAX_ROOM_LENGTH: usize = 32;
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const PRESSED_BUTTON_COLOR: Color = Color::rgb(0.35, 0.55, 0.35);
 
 pub struct MainMenuPlugin;
 
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(setup_main_menu.in_schedule(OnEnter(AppState::MainMenu)))
-            .add_system(spin_main_menu_cube.in_set(OnUpdate(AppState::MainMenu)))
-            .add_system(setdown_main_menu.in_schedule(OnExit(AppState::MainMenu)));
+        app.add_state::<MenuScreen>()
+            .init_resource::<SettingsPage>()
+            .init_resource::<Rebinding>()
+            .add_system(setup_main_menu.in_schedule(OnEnter(AppState::MainMenu)))
+            .add_systems(
+                (
+                    spin_main_menu_cube,
+                    pick_loadout,
+                    highlight_buttons,
+                    press_menu_buttons,
+                )
+                    .in_set(OnUpdate(AppState::MainMenu)),
+            )
+            .add_system(setdown_main_menu.in_schedule(OnExit(AppState::MainMenu)))
+            .add_system(setup_title_screen.in_schedule(OnEnter(MenuScreen::Title)))
+            .add_system(setup_play_screen.in_schedule(OnEnter(MenuScreen::Play)))
+            .add_system(setup_lobby_screen.in_schedule(OnEnter(MenuScreen::Lobby)))
+            .add_system(setup_settings_screen.in_schedule(OnEnter(MenuScreen::Settings)))
+            .add_systems(
+                (edit_room_name, describe_match_configuration)
+                    .chain()
+                    .in_set(OnUpdate(MenuScreen::Play)),
+            )
+            .add_system(describe_lobby.in_set(OnUpdate(MenuScreen::Lobby)))
+            .add_systems(
+                (
+                    // Runs first so the click which starts listening isn't bound immediately
+                    capture_rebinding.before(press_menu_buttons),
+                    describe_settings,
+                    describe_bindings,
+                )
+                    .in_set(OnUpdate(MenuScreen::Settings)),
+            );
+
+        for screen in [
+            MenuScreen::Title,
+            MenuScreen::Play,
+            MenuScreen::Lobby,
+            MenuScreen::Settings,
+        ] {
+            app.add_system(setdown_menu_screen.in_schedule(OnExit(screen)));
+        }
+    }
+}
+
+/// The screen of the main menu currently shown.
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum MenuScreen {
+    /// Choose between playing, changing settings and quitting.
+    #[default]
+    Title,
+    /// Choose a room, number of players and loadout.
+    Play,
+    /// Connected to a room, waiting for other players to join.
+    Lobby,
+    /// Change and save the settings, one page at a time.
+    Settings,
+}
+
+/// The page of the settings screen currently shown.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SettingsPage {
+    #[default]
+    Controls,
+    Gamepad,
+    Graphics,
+    Audio,
+    Matchmaking,
+}
+
+impl SettingsPage {
+    const ALL: [Self; 5] = [
+        Self::Controls,
+        Self::Gamepad,
+        Self::Graphics,
+        Self::Audio,
+        Self::Matchmaking,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::Controls => "Controls",
+            Self::Gamepad => "Gamepad",
+            Self::Graphics => "Graphics",
+            Self::Audio => "Audio",
+            Self::Matchmaking => "Matchmaking",
+        }
+    }
+
+    /// The settings shown on this page, in order.
+    fn settings(self) -> &'static [Setting] {
+        match self {
+            Self::Controls => &[Setting::PointerSensitivity],
+            Self::Gamepad => &[
+                Setting::StickDeadzone,
+                Setting::StickResponseCurve,
+                Setting::StickLookSpeed,
+            ],
+            Self::Graphics => &[
+                Setting::WindowMode,
+                Setting::Vsync,
+                Setting::Msaa,
+                Setting::Particles,
+                Setting::FieldOfView,
+                Setting::ViewBob,
+                Setting::AimZoom,
+            ],
+            Self::Audio => &[Setting::Volume],
+            Self::Matchmaking => &[Setting::Players, Setting::GameMode, Setting::FriendlyFire],
+        }
+    }
+}
+
+/// The binding waiting for the next key or button to be pressed, and what happened to the
+/// last binding changed.
+#[derive(Resource, Default)]
+struct Rebinding {
+    listening: Option<(UserAction, BindingSlot)>,
+    message: String,
+}
+
+/// A value in `Config` which can be changed from the settings screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Setting {
+    PointerSensitivity,
+    StickDeadzone,
+    StickResponseCurve,
+    StickLookSpeed,
+    WindowMode,
+    Vsync,
+    Msaa,
+    Particles,
+    FieldOfView,
+    ViewBob,
+    AimZoom,
+    Volume,
+    Players,
+    GameMode,
+    FriendlyFire,
+}
+
+impl Setting {
+    fn label(self) -> &'static str {
+        match self {
+            Self::PointerSensitivity => "Mouse Sensitivity",
+            Self::StickDeadzone => "Stick Deadzone",
+            Self::StickResponseCurve => "Stick Response Curve",
+            Self::StickLookSpeed => "Stick Look Speed",
+            Self::WindowMode => "Window Mode",
+            Self::Vsync => "Vsync",
+            Self::Msaa => "Anti-Aliasing",
+            Self::Particles => "Particle Detail",
+            Self::FieldOfView => "Field of View",
+            Self::ViewBob => "View Bob",
+            Self::AimZoom => "Aim Zoom",
+            Self::Volume => "Volume",
+            Self::Players => "Players",
+            Self::GameMode => "Game Mode",
+            Self::FriendlyFire => "Friendly Fire",
+        }
+    }
+
+    /// Describes the current value of this setting.
+    fn describe(self, config: &Config) -> String {
+        let graphics = &config.graphics;
+        let sticks = &config.controls.gamepad;
+
+        match self {
+            Self::PointerSensitivity => format!("{:.2}", config.controls.pointer_sensitivity),
+            Self::StickDeadzone => format!("{:.0}%", sticks.deadzone * 100.0),
+            Self::StickResponseCurve if sticks.response_curve == 1.0 => "Linear".to_owned(),
+            Self::StickResponseCurve => format!("Power of {:.2}", sticks.response_curve),
+            Self::StickLookSpeed => format!("{:.1} radians per second", sticks.look_speed),
+            Self::WindowMode => format!("{:?}", graphics.mode),
+            Self::Vsync if graphics.vsync => "On".to_owned(),
+            Self::Vsync => "Off".to_owned(),
+            Self::Msaa => format!("{:?}", graphics.msaa),
+            // Particle effects are only built when the game starts
+            Self::Particles => format!("{:?} (applies after restarting)", graphics.particles),
+            Self::FieldOfView => format!("{:.0} degrees", graphics.camera.fov),
+            Self::ViewBob => format!("{:.0}%", graphics.camera.view_bob * 100.0),
+            Self::AimZoom => format!("{:.2}x", graphics.camera.aim_zoom),
+            Self::Volume => format!("{:.0}%", config.audio.volume * 100.0),
+            Self::Players => config.matchmaking.players.to_string(),
+            Self::GameMode => match config.matchmaking.mode {
+                GameMode::FreeForAll => "Free-for-all".to_owned(),
+                GameMode::TeamVsTeam => "Team vs Team".to_owned(),
+            },
+            Self::FriendlyFire => match config.matchmaking.friendly_fire {
+                FriendlyFire::Off => "Off".to_owned(),
+                FriendlyFire::On => "On".to_owned(),
+                FriendlyFire::Reduced(fraction) => format!("{:.0}% damage", fraction * 100.0),
+            },
+        }
+    }
+
+    /// Changes this setting to its next or previous value.
+    fn adjust(self, config: &mut Config, forwards: bool) {
+        let graphics = &mut config.graphics;
+        let sticks = &mut config.controls.gamepad;
+
+        match self {
+            Self::PointerSensitivity => step(
+                &mut config.controls.pointer_sensitivity,
+                0.05,
+                0.05..=5.0,
+                forwards,
+            ),
+            Self::StickDeadzone => step(&mut sticks.deadzone, 0.05, 0.0..=0.5, forwards),
+            Self::StickResponseCurve => step(&mut sticks.response_curve, 0.25, 1.0..=3.0, forwards),
+            Self::StickLookSpeed => step(&mut sticks.look_speed, 0.5, 0.5..=10.0, forwards),
+            Self::WindowMode => {
+                graphics.mode = cycle(
+                    &[
+                        WindowMode::Windowed,
+                        WindowMode::BorderlessFullscreen,
+                        WindowMode::Fullscreen,
+                    ],
+                    graphics.mode,
+                    forwards,
+                )
+            }
+            Self::Vsync => graphics.vsync = !graphics.vsync,
+            Self::Msaa => {
+                graphics.msaa = cycle(
+                    &[Msaa::Off, Msaa::X2, Msaa::X4, Msaa::X8],
+                    graphics.msaa,
+                    forwards,
+                )
+            }
+            Self::Particles => {
+                graphics.particles = cycle(
+                    &[
+                        ParticleDetail::Low,
+                        ParticleDetail::Medium,
+                        ParticleDetail::High,
+                    ],
+                    graphics.particles,
+                    forwards,
+                )
+            }
+            Self::FieldOfView => step(&mut graphics.camera.fov, 5.0, 50.0..=110.0, forwards),
+            Self::ViewBob => step(&mut graphics.camera.view_bob, 0.25, 0.0..=2.0, forwards),
+            Self::AimZoom => step(&mut graphics.camera.aim_zoom, 0.25, 1.0..=4.0, forwards),
+            Self::Volume => step(&mut config.audio.volume, 0.1, 0.0..=1.0, forwards),
+            Self::Players => {
+                let players = config.matchmaking.players.get();
+                let players = if forwards {
+                    usize::min(players + 1, MAX_PLAYERS)
+                } else {
+                    usize::max(players - 1, 1)
+                };
+
+                if let Some(players) = NonZeroUsize::new(players) {
+                    config.matchmaking.players = players;
+                }
+            }
+            Self::GameMode => {
+                config.matchmaking.mode = cycle(
+                    &[GameMode::FreeForAll, GameMode::TeamVsTeam],
+                    config.matchmaking.mode,
+                    forwards,
+                )
+            }
+            Self::FriendlyFire => {
+                config.matchmaking.friendly_fire = cycle(
+                    &[
+                        FriendlyFire::Off,
+                        FriendlyFire::Reduced(0.5),
+                        FriendlyFire::On,
+                    ],
+                    config.matchmaking.friendly_fire,
+                    forwards,
+                )
+            }
+        }
     }
 }
 
+/// Moves `value` up or down by `increment`, without leaving `range`.
+fn step(value: &mut f32, increment: f32, range: RangeInclusive<f32>, forwards: bool) {
+    let increment = if forwards { increment } else { -increment };
+
+    // Snap to a multiple of the increment so repeated steps don't accumulate rounding errors
+    let stepped = ((*value + increment) / increment).round() * increment;
+
+    *value = stepped.clamp(*range.start(), *range.end());
+}
+
+/// The option after (or before) `current`, wrapping around at either end.
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T, forwards: bool) -> T {
+    let index = options
+        .iter()
+        .position(|option| *option == current)
+        .unwrap_or(0);
+
+    let step = if forwards { 1 } else { options.len() - 1 };
+    options[(index + step) % options.len()]
+}
+
 /// Marks an entity as only relevant for the Main Menu state.
 #[derive(Component)]
 struct MainMenuEntity;
 
+/// Marks an entity as only relevant for the current `MenuScreen`.
+#[derive(Component)]
+struct MenuScreenEntity;
+
 #[derive(Component)]
 struct MainMenuCube;
 
+/// Marks the text describing the chosen loadout.
+#[derive(Component)]
+struct LoadoutText;
+
+/// Marks the text describing the room and number of players.
+#[derive(Component)]
+struct MatchConfigurationText;
+
+/// Marks the text describing the progress of joining a match.
+#[derive(Component)]
+struct LobbyText;
+
+/// Marks the text describing the value of a setting.
+#[derive(Component)]
+struct SettingText(Setting);
+
+/// Marks the label of a button showing the input bound to an action.
+#[derive(Component)]
+struct BindingText(UserAction, BindingSlot);
+
+/// Marks the text describing what happened to the last binding changed.
+#[derive(Component)]
+struct RebindingText;
+
+/// What happens when a menu button is pressed.
+#[derive(Component, Clone, Copy, Debug)]
+enum MenuButton {
+    /// Go to another screen.
+    Open(MenuScreen),
+    Quit,
+    MorePlayers,
+    FewerPlayers,
+    PreviousWeapon,
+    NextWeapon,
+    /// Show another page of settings.
+    SettingsPage(SettingsPage),
+    PreviousValue(Setting),
+    NextValue(Setting),
+    /// Bind the next input pressed to an action.
+    Rebind(UserAction, BindingSlot),
+    ResetBindings,
+}
+
 /// Construct the Main Menu
 fn setup_main_menu(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    assets: Res<AssetServer>,
 ) {
     // Spawn a cube
     let cube_handle = meshes.add(Mesh::from(shape::Cube { size: 1.0 }));
@@ -63,30 +422,595 @@ fn setup_main_menu(
         AudioReceiver,
         MainMenuEntity,
     ));
+}
 
-    // Create some prompt text
-    commands.spawn((
-        TextBundle::from_section(
-            "Waiting for players to join...",
-            TextStyle {
-                font: assets.load("fira_mono.ttf"),
-                font_size: 48.0,
-                color: Color::BLACK,
-            },
-        )
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            position: UiRect {
-                bottom: Val::Percent(5.0),
-                left: Val::Percent(5.0),
+/// Spawns the root of a menu screen, which arranges its contents in a column down the left.
+fn spawn_screen(commands: &mut Commands, spawn_contents: impl FnOnce(&mut ChildBuilder)) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Percent(5.0),
+                        left: Val::Percent(5.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexStart,
+                    ..default()
+                },
                 ..default()
             },
+            MenuScreenEntity,
+        ))
+        .with_children(spawn_contents);
+}
+
+fn text_style(assets: &AssetServer, font_size: f32) -> TextStyle {
+    TextStyle {
+        font: assets.load("fira_mono.ttf"),
+        font_size,
+        color: Color::BLACK,
+    }
+}
+
+/// Spawns a line of text, along with a `marker` so it can be updated later.
+fn spawn_text(parent: &mut ChildBuilder, style: TextStyle, value: &str, marker: impl Bundle) {
+    parent.spawn((
+        TextBundle::from_section(value, style).with_style(Style {
+            margin: UiRect::vertical(Val::Px(8.0)),
             ..default()
         }),
-        MainMenuEntity,
+        marker,
     ));
 }
 
+fn spawn_button(parent: &mut ChildBuilder, assets: &AssetServer, label: &str, action: MenuButton) {
+    spawn_labelled_button(parent, assets, label, 32.0, action, ());
+}
+
+/// Spawns a button, along with a `marker` on its label so the label can be updated later.
+fn spawn_labelled_button(
+    parent: &mut ChildBuilder,
+    assets: &AssetServer,
+    label: &str,
+    font_size: f32,
+    action: MenuButton,
+    marker: impl Bundle,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    margin: UiRect::all(Val::Px(4.0)),
+                    padding: UiRect::new(Val::Px(16.0), Val::Px(16.0), Val::Px(8.0), Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            action,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        color: Color::WHITE,
+                        ..text_style(assets, font_size)
+                    },
+                ),
+                marker,
+            ));
+        });
+}
+
+/// Spawns a row of buttons.
+fn spawn_buttons(parent: &mut ChildBuilder, assets: &AssetServer, buttons: &[(&str, MenuButton)]) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for (label, action) in buttons {
+                spawn_button(parent, assets, label, *action);
+            }
+        });
+}
+
+fn setup_title_screen(mut commands: Commands, assets: Res<AssetServer>) {
+    spawn_screen(&mut commands, |parent| {
+        spawn_text(parent, text_style(&assets, 64.0), "Muskrats at Dawn", ());
+        spawn_button(parent, &assets, "Play", MenuButton::Open(MenuScreen::Play));
+        spawn_button(
+            parent,
+            &assets,
+            "Settings",
+            MenuButton::Open(MenuScreen::Settings),
+        );
+        spawn_button(parent, &assets, "Quit", MenuButton::Quit);
+    });
+}
+
+fn setup_play_screen(mut commands: Commands, assets: Res<AssetServer>) {
+    spawn_screen(&mut commands, |parent| {
+        spawn_text(parent, text_style(&assets, 48.0), "Play", ());
+
+        spawn_text(
+            parent,
+            text_style(&assets, 32.0),
+            "",
+            MatchConfigurationText,
+        );
+
+        spawn_buttons(
+            parent,
+            &assets,
+            &[
+                ("Fewer Players", MenuButton::FewerPlayers),
+                ("More Players", MenuButton::MorePlayers),
+            ],
+        );
+
+        spawn_text(parent, text_style(&assets, 32.0), "", LoadoutText);
+
+        spawn_buttons(
+            parent,
+            &assets,
+            &[
+                ("Previous Weapon", MenuButton::PreviousWeapon),
+                ("Next Weapon", MenuButton::NextWeapon),
+            ],
+        );
+
+        spawn_buttons(
+            parent,
+            &assets,
+            &[
+                ("Connect", MenuButton::Open(MenuScreen::Lobby)),
+                ("Back", MenuButton::Open(MenuScreen::Title)),
+            ],
+        );
+    });
+}
+
+fn setup_lobby_screen(mut commands: Commands, assets: Res<AssetServer>) {
+    spawn_screen(&mut commands, |parent| {
+        spawn_text(
+            parent,
+            text_style(&assets, 48.0),
+            "Connecting...",
+            LobbyText,
+        );
+
+        spawn_text(parent, text_style(&assets, 32.0), "", LoadoutText);
+
+        spawn_button(
+            parent,
+            &assets,
+            "Cancel",
+            MenuButton::Open(MenuScreen::Play),
+        );
+    });
+}
+
+fn setup_settings_screen(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    page: Res<SettingsPage>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    *rebinding = Rebinding::default();
+
+    spawn_screen(&mut commands, |parent| {
+        spawn_text(
+            parent,
+            text_style(&assets, 48.0),
+            &format!("Settings: {}", page.title()),
+            (),
+        );
+
+        let pages = SettingsPage::ALL.map(|page| (page.title(), MenuButton::SettingsPage(page)));
+        spawn_buttons(parent, &assets, &pages);
+
+        // Each setting is a row of buttons to change it, followed by its current value
+        for setting in page.settings() {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_button(parent, &assets, "<", MenuButton::PreviousValue(*setting));
+                    spawn_button(parent, &assets, ">", MenuButton::NextValue(*setting));
+                    spawn_text(parent, text_style(&assets, 32.0), "", SettingText(*setting));
+                });
+        }
+
+        if *page == SettingsPage::Controls {
+            spawn_text(parent, text_style(&assets, 24.0), "", RebindingText);
+            spawn_bindings(parent, &assets);
+            spawn_button(parent, &assets, "Reset Bindings", MenuButton::ResetBindings);
+        }
+
+        spawn_button(parent, &assets, "Back", MenuButton::Open(MenuScreen::Title));
+    });
+}
+
+/// Spawns a button for each binding of every action, arranged in two columns.
+fn spawn_bindings(parent: &mut ChildBuilder, assets: &AssetServer) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                flex_wrap: FlexWrap::Wrap,
+                size: Size::width(Val::Px(1100.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for action in all::<UserAction>() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            size: Size::width(Val::Percent(50.0)),
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(
+                            TextBundle::from_section(action.to_string(), text_style(assets, 24.0))
+                                .with_style(Style {
+                                    size: Size::width(Val::Px(200.0)),
+                                    ..default()
+                                }),
+                        );
+
+                        for slot in [BindingSlot::Primary, BindingSlot::Secondary] {
+                            spawn_labelled_button(
+                                parent,
+                                assets,
+                                "",
+                                24.0,
+                                MenuButton::Rebind(action, slot),
+                                BindingText(action, slot),
+                            );
+                        }
+                    });
+            }
+        });
+}
+
+/// Shows which buttons are being hovered over or pressed.
+fn highlight_buttons(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, mut color) in buttons.iter_mut() {
+        *color = match interaction {
+            Interaction::Clicked => PRESSED_BUTTON_COLOR,
+            Interaction::Hovered => HOVERED_BUTTON_COLOR,
+            Interaction::None => BUTTON_COLOR,
+        }
+        .into();
+    }
+}
+
+/// Performs the action of every button pressed.
+fn press_menu_buttons(
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+    mut exit_events: EventWriter<AppExit>,
+    mut config: ResMut<MatchConfiguration>,
+    mut settings: ResMut<Config>,
+    mut settings_page: ResMut<SettingsPage>,
+    mut rebinding: ResMut<Rebinding>,
+    mut loadout: ResMut<Loadout>,
+    pending_loadouts: Res<PendingLoadouts>,
+    weapon_library: Res<WeaponLibrary>,
+    weapon_definitions: Res<Assets<WeaponDefinition>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        match button {
+            // A room must be named before connecting to it
+            MenuButton::Open(MenuScreen::Lobby) if config.room_id.is_empty() => {}
+            MenuButton::Open(screen) => next_screen.set(*screen),
+            MenuButton::Quit => exit_events.send(AppExit),
+            MenuButton::MorePlayers => config.players = usize::min(config.players + 1, MAX_PLAYERS),
+            MenuButton::FewerPlayers => config.players = usize::max(config.players - 1, 1),
+            MenuButton::PreviousWeapon | MenuButton::NextWeapon => {
+                let forwards = matches!(button, MenuButton::NextWeapon);
+                cycle_weapon(
+                    &mut loadout,
+                    &pending_loadouts,
+                    &weapon_library,
+                    &weapon_definitions,
+                    forwards,
+                );
+            }
+            MenuButton::SettingsPage(page) => {
+                *settings_page = *page;
+                // Re-entering the screen rebuilds it with the settings on the new page
+                next_screen.set(MenuScreen::Settings);
+            }
+            MenuButton::PreviousValue(setting) | MenuButton::NextValue(setting) => {
+                let forwards = matches!(button, MenuButton::NextValue(_));
+                setting.adjust(&mut settings, forwards);
+
+                // The next match uses the new number of players too
+                if *setting == Setting::Players {
+                    config.players = settings.matchmaking.players.get();
+                }
+
+                save_settings(&settings);
+            }
+            MenuButton::Rebind(action, slot) => {
+                rebinding.listening = Some((*action, *slot));
+                rebinding.message = format!(
+                    "Press a key or button for {action} (Escape to cancel, Delete to clear)"
+                );
+            }
+            MenuButton::ResetBindings => {
+                settings.controls.reset_bindings();
+                *rebinding = Rebinding {
+                    listening: None,
+                    message: "Restored the default bindings".to_owned(),
+                };
+
+                save_settings(&settings);
+            }
+        }
+    }
+}
+
+fn save_settings(config: &Config) {
+    if let Err(error) = config.try_save() {
+        log::error!("Unable to save settings: {error}");
+    }
+}
+
+/// Binds the next key, mouse button or gamepad button pressed to the action waiting to be rebound.
+fn capture_rebinding(
+    keyboard: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Config>,
+) {
+    let Some((action, slot)) = rebinding.listening else {
+        return;
+    };
+
+    let input = match (
+        keyboard.get_just_pressed().next(),
+        mouse.get_just_pressed().next(),
+        gamepad_buttons.get_just_pressed().next(),
+    ) {
+        (Some(KeyCode::Escape), _, _) => {
+            *rebinding = Rebinding::default();
+            return;
+        }
+        (Some(KeyCode::Delete), _, _) => None,
+        (Some(key), _, _) => Some(UserInput::from(*key)),
+        (None, Some(button), _) => Some(UserInput::from(*button)),
+        (None, None, Some(button)) => Some(UserInput::from(button.button_type)),
+        (None, None, None) => return,
+    };
+
+    let swapped = settings.controls.rebind(action, slot, input);
+
+    let message = match (input, swapped) {
+        (None, _) => format!("Cleared a binding of {action}"),
+        (Some(input), Some(other)) => {
+            format!("{input} was bound to {other}, so the bindings were swapped")
+        }
+        (Some(input), None) => format!("Bound {input} to {action}"),
+    };
+
+    *rebinding = Rebinding {
+        listening: None,
+        message,
+    };
+
+    save_settings(&settings);
+}
+
+/// Edits the name of the room to connect to with whatever is typed.
+fn edit_room_name(
+    mut characters: EventReader<ReceivedCharacter>,
+    mut config: ResMut<MatchConfiguration>,
+) {
+    for ReceivedCharacter {
+        char: character, ..
+    } in characters.iter()
+    {
+        match *character {
+            // Backspace, which some platforms report as delete
+            '\u{8}' | '\u{7f}' => {
+                config.room_id.pop();
+            }
+            character if character.is_ascii_alphanumeric() || "-_".contains(character) => {
+                if config.room_id.len() < MAX_ROOM_LENGTH {
+                    config.room_id.push(character);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Describes the room and number of players which will be connected to.
+fn describe_match_configuration(
+    config: Res<MatchConfiguration>,
+    mut query: Query<&mut Text, With<MatchConfigurationText>>,
+) {
+    let description = format!(
+        "Room: {}_ (type to change)\nPlayers: {}",
+        config.room_id, config.players
+    );
+
+    for mut text in query.iter_mut() {
+        // Avoid triggering change detection when the text is already correct
+        if text.sections[0].value != description {
+            text.sections[0].value = description.clone();
+        }
+    }
+}
+
+/// Describes how many more players need to join before the match starts.
+fn describe_lobby(
+    config: Res<MatchConfiguration>,
+    socket: Option<Res<SocketResource>>,
+    mut query: Query<&mut Text, With<LobbyText>>,
+) {
+    let remaining = socket.and_then(|socket| socket.remaining_players(&config));
+
+    let description = match remaining {
+        None => "Connecting...".to_owned(),
+        Some(0) => "Starting the match...".to_owned(),
+        Some(remaining) => format!("Waiting for {remaining} more player(s) to join..."),
+    };
+
+    for mut text in query.iter_mut() {
+        if text.sections[0].value != description {
+            text.sections[0].value = description.clone();
+        }
+    }
+}
+
+/// Describes the current value of every setting shown.
+fn describe_settings(config: Res<Config>, mut query: Query<(&mut Text, &SettingText)>) {
+    for (mut text, SettingText(setting)) in query.iter_mut() {
+        let description = format!("{}: {}", setting.label(), setting.describe(&config));
+
+        // Avoid triggering change detection when the text is already correct
+        if text.sections[0].value != description {
+            text.sections[0].value = description;
+        }
+    }
+}
+
+/// Describes the input bound to every action, and what happened to the last binding changed.
+fn describe_bindings(
+    config: Res<Config>,
+    rebinding: Res<Rebinding>,
+    mut bindings: Query<(&mut Text, &BindingText)>,
+    mut messages: Query<&mut Text, (With<RebindingText>, Without<BindingText>)>,
+) {
+    for (mut text, BindingText(action, slot)) in bindings.iter_mut() {
+        let description = if rebinding.listening == Some((*action, *slot)) {
+            "...".to_owned()
+        } else {
+            config
+                .controls
+                .binding_for(*action)
+                .get(*slot)
+                .map_or("-".to_owned(), |input| input.to_string())
+        };
+
+        // Avoid triggering change detection when the text is already correct
+        if text.sections[0].value != description {
+            text.sections[0].value = description;
+        }
+    }
+
+    for mut text in messages.iter_mut() {
+        if text.sections[0].value != rebinding.message {
+            text.sections[0].value = rebinding.message.clone();
+        }
+    }
+}
+
+/// Changes the chosen weapon to the next or previous available weapon, unless the loadout has
+/// already been shared with other players.
+fn cycle_weapon(
+    loadout: &mut Loadout,
+    pending_loadouts: &PendingLoadouts,
+    weapon_library: &WeaponLibrary,
+    weapon_definitions: &Assets<WeaponDefinition>,
+    forwards: bool,
+) {
+    let weapons = weapon_library.all(weapon_definitions);
+
+    if weapons.is_empty() || pending_loadouts.shared {
+        return;
+    }
+
+    let current = weapons
+        .iter()
+        .position(|weapon| weapon.id == loadout.weapon)
+        .unwrap_or(0);
+
+    let step = if forwards { 1 } else { weapons.len() - 1 };
+    let weapon = weapons[(current + step) % weapons.len()];
+    loadout.weapon = weapon.id.clone();
+}
+
+/// Cycles through the available weapons with the arrow keys, and describes the chosen weapon.
+fn pick_loadout(
+    key: Res<Input<KeyCode>>,
+    weapon_library: Res<WeaponLibrary>,
+    weapon_definitions: Res<Assets<WeaponDefinition>>,
+    pending_loadouts: Res<PendingLoadouts>,
+    mut loadout: ResMut<Loadout>,
+    mut query: Query<&mut Text, With<LoadoutText>>,
+) {
+    match (
+        key.just_pressed(KeyCode::Left),
+        key.just_pressed(KeyCode::Right),
+    ) {
+        (true, false) | (false, true) => cycle_weapon(
+            &mut loadout,
+            &pending_loadouts,
+            &weapon_library,
+            &weapon_definitions,
+            key.just_pressed(KeyCode::Right),
+        ),
+        _ => {}
+    }
+
+    let weapons = weapon_library.all(&weapon_definitions);
+
+    let Some(weapon) = weapons
+        .iter()
+        .find(|weapon| weapon.id == loadout.weapon)
+        .or(weapons.first())
+    else {
+        return;
+    };
+
+    let hint = if pending_loadouts.shared {
+        "locked in"
+    } else {
+        "Left/Right to change"
+    };
+
+    let description = format!("Weapon: {} ({hint})", weapon.name);
+
+    for mut text in query.iter_mut() {
+        // Avoid triggering change detection when the text is already correct
+        if text.sections[0].value != description {
+            text.sections[0].value = description.clone();
+        }
+    }
+}
+
 /// Animates a cube on the main menu
 fn spin_main_menu_cube(time: Res<Time>, mut query: Query<&mut Transform, With<MainMenuCube>>) {
     let t = time.elapsed_seconds();
@@ -101,20 +1025,50 @@ fn spin_main_menu_cube(time: Res<Time>, mut query: Query<&mut Transform, With<Ma
     }
 }
 
+/// Clean-Up the contents of a screen when leaving it
+fn setdown_menu_screen(mut commands: Commands, query: Query<Entity, With<MenuScreenEntity>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
 /// Clean-Up assets from the main-menu
-fn setdown_main_menu(mut commands: Commands, query: Query<Entity, With<MainMenuEntity>>) {
+fn setdown_main_menu(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<MainMenuEntity>, With<MenuScreenEntity>)>>,
+) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
 }
 
-/*
-/// React to player input to start the game.
-fn start_game(key: Res<Input<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
-    let any_key_pressed = key.get_just_pressed().next().is_some();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_stay_within_their_range() {
+        let mut config = Config::default();
+        config.audio.volume = 0.95;
+
+        Setting::Volume.adjust(&mut config, true);
+        assert_eq!(config.audio.volume, 1.0);
+
+        for _ in 0..20 {
+            Setting::Volume.adjust(&mut config, false);
+        }
+        assert_eq!(config.audio.volume, 0.0);
+    }
+
+    #[test]
+    fn options_wrap_around() {
+        let mut config = Config::default();
+        config.graphics.msaa = Msaa::X8;
+
+        Setting::Msaa.adjust(&mut config, true);
+        assert_eq!(config.graphics.msaa, Msaa::Off);
 
-    if any_key_pressed {
-        next_state.set(AppState::InGame);
+        Setting::Msaa.adjust(&mut config, false);
+        assert_eq!(config.graphics.msaa, Msaa::X8);
     }
 }
- */
@@ -0,0 +1,156 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use ggrs::PlayerType;
+use matchbox_socket::{PeerId, WebRtcSocket};
+use serde::{Deserialize, Serialize};
+
+use crate::{controller::MovementProfile, firearm::DEFAULT_WEAPON, team::Teams};
+
+/// Settings chosen by the host which every peer must agree on before the session starts.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct SessionSettings {
+    pub movement: MovementProfile,
+    /// Seed of the `RollbackRng`, chosen fresh by the host for every session.
+    pub seed: u64,
+    pub teams: Teams,
+}
+
+impl SessionSettings {
+    /// Settings for a session of `players` players.
+    pub fn from_config(config: &crate::config::Config, players: usize) -> Self {
+        // The seed only needs to differ between sessions, every peer receives it from the host
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+
+        let matchmaking = &config.matchmaking;
+
+        Self {
+            movement: config.movement.selected_profile(),
+            seed,
+            teams: Teams::assign(matchmaking.mode, matchmaking.friendly_fire, players),
+        }
+    }
+}
+
+/// The equipment a player has chosen to start the match with.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Loadout {
+    /// Id of the weapon definition to equip.
+    pub weapon: String,
+}
+
+impl Default for Loadout {
+    fn default() -> Self {
+        Self {
+            weapon: DEFAULT_WEAPON.to_owned(),
+        }
+    }
+}
+
+/// The loadout of every player in the session, indexed by player handle.
+#[derive(Resource, Debug)]
+pub struct Loadouts(Vec<Loadout>);
+
+impl Loadouts {
+    pub fn get(&self, player_handle: usize) -> Option<&Loadout> {
+        self.0.get(player_handle)
+    }
+}
+
+/// Loadouts shared by peers in the lobby, collected until every player has chosen.
+#[derive(Resource, Default)]
+pub struct PendingLoadouts {
+    received: HashMap<PeerId, Loadout>,
+    /// The local loadout is being shared with every peer, so can no longer change.
+    pub shared: bool,
+}
+
+impl PendingLoadouts {
+    pub fn insert(&mut self, peer: PeerId, loadout: Loadout) {
+        self.received.insert(peer, loadout);
+    }
+
+    /// Orders every loadout by player handle, once one has been received from every peer.
+    pub fn complete(&self, socket: &WebRtcSocket, local: &Loadout) -> Option<Loadouts> {
+        socket
+            .players()
+            .into_iter()
+            .filter(|player| !matches!(player, PlayerType::Spectator(_)))
+            .map(|player| match player {
+                PlayerType::Remote(peer) => self.received.get(&peer).cloned(),
+                _ => Some(local.clone()),
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(Loadouts)
+    }
+}
+
+/// Peers which have confirmed they have everything needed to start the session.
+#[derive(Resource, Default)]
+pub struct ReadyPeers(HashSet<PeerId>);
+
+impl ReadyPeers {
+    pub fn insert(&mut self, peer: PeerId) {
+        self.0.insert(peer);
+    }
+
+    /// Checks if every connected peer has confirmed it is ready.
+    pub fn all(&self, socket: &WebRtcSocket) -> bool {
+        socket.connected_peers().all(|peer| self.0.contains(&peer))
+    }
+}
+
+/// Messages exchanged between peers in the lobby, before the GGRS session takes over the socket.
+#[derive(Serialize, Deserialize, Debug)]
+pub enum LobbyMessage {
+    SessionSettings(SessionSettings),
+    Loadout(Loadout),
+    /// The sender has the session settings and every loadout, so is ready to start the session.
+    Ready,
+}
+
+impl LobbyMessage {
+    fn encode(&self) -> Box<[u8]> {
+        serde_json::to_vec(self)
+            .expect("Lobby messages must be serializable")
+            .into_boxed_slice()
+    }
+
+    fn decode(packet: &[u8]) -> Option<Self> {
+        serde_json::from_slice(packet).ok()
+    }
+}
+
+/// Checks if the local peer hosts the match, which is the peer given the first player handle.
+pub fn is_host(socket: &WebRtcSocket) -> bool {
+    matches!(socket.players().first(), Some(PlayerType::Local))
+}
+
+/// Sends a `LobbyMessage` to every connected peer.
+///
+/// The socket is unreliable, so messages are sent again every frame until every peer is ready.
+pub fn broadcast(socket: &mut WebRtcSocket, message: &LobbyMessage) {
+    let packet = message.encode();
+    let peers = socket.connected_peers().collect::<Vec<_>>();
+
+    for peer in peers {
+        socket.send(packet.clone(), peer);
+    }
+}
+
+/// Receives all pending packets, decoding any `LobbyMessage`s.
+///
+/// Packets which aren't lobby messages come from peers which have already started the session.
+/// They're discarded, but GGRS retransmits anything it needs to synchronise.
+pub fn receive(socket: &mut WebRtcSocket) -> Vec<(PeerId, Option<LobbyMessage>)> {
+    socket
+        .receive()
+        .into_iter()
+        .map(|(peer, packet)| (peer, LobbyMessage::decode(&packet)))
+        .collect()
+}
@@ -3,7 +3,14 @@ use bevy_ggrs::Session;
 use ggrs::{Config, SessionBuilder};
 use matchbox_socket::{PeerId, PeerState, WebRtcSocket};
 
-use crate::AppState;
+use crate::{firearm::WeaponLibrary, random::RollbackRng, stats::MatchStats, AppState};
+
+pub use lobby::*;
+
+mod lobby;
+
+/// Frames between a player's input being captured and it being simulated.
+pub const INPUT_DELAY: usize = 2;
 
 #[derive(Resource)]
 pub struct MatchConfiguration {
@@ -14,6 +21,14 @@ pub struct MatchConfiguration {
 #[derive(Default, Resource)]
 pub struct SocketResource(Option<WebRtcSocket>);
 
+impl SocketResource {
+    /// Number of players still needed before the match can start, if connecting to a match.
+    pub fn remaining_players(&self, config: &MatchConfiguration) -> Option<usize> {
+        let connected_peers = self.0.as_ref()?.connected_peers().count();
+        Some(config.players.saturating_sub(connected_peers + 1))
+    }
+}
+
 #[derive(Debug)]
 pub struct GGRSConfig;
 
@@ -28,7 +43,11 @@ pub fn start_matchbox_socket(
     config: Res<MatchConfiguration>,
     game_settings: Res<crate::config::Config>,
 ) {
-    let room_url = format!("{}/{}", game_settings.matchmaking.server, config.room_id);
+    // Peers are only grouped with others expecting the same number of players
+    let room_url = format!(
+        "{}/{}?next={}",
+        game_settings.matchmaking.server, config.room_id, config.players
+    );
 
     info!("connecting to matchbox server: {:?}", room_url);
     let (socket, message_loop) = WebRtcSocket::new_ggrs(room_url);
@@ -41,6 +60,16 @@ pub fn start_matchbox_socket(
     commands.insert_resource(SocketResource(Some(socket)));
 }
 
+/// Abandons the match being connected to, forgetting anything agreed with its peers.
+pub fn leave_lobby(mut commands: Commands) {
+    info!("Leaving the lobby");
+
+    commands.insert_resource(SocketResource::default());
+    commands.insert_resource(PendingLoadouts::default());
+    commands.insert_resource(ReadyPeers::default());
+    commands.remove_resource::<SessionSettings>();
+}
+
 pub fn watch_for_connected_peers(mut socket: ResMut<SocketResource>) {
     // regularly call update_peers to update the list of connected peers
     for (peer, new_state) in socket.0.as_mut().unwrap().update_peers() {
@@ -58,9 +87,21 @@ pub fn start_game_when_ready(
     mut next_state: ResMut<NextState<AppState>>,
     config: Res<MatchConfiguration>,
     game_settings: Res<crate::config::Config>,
+    session_settings: Option<Res<SessionSettings>>,
+    loadout: Res<Loadout>,
+    mut pending_loadouts: ResMut<PendingLoadouts>,
+    mut ready_peers: ResMut<ReadyPeers>,
+    weapon_library: Res<WeaponLibrary>,
+    asset_server: Res<AssetServer>,
 ) {
-    let connected_peers = socket.0.as_ref().unwrap().connected_peers().count();
-    let remaining = config.players - (connected_peers + 1);
+    // Players can't be equipped until their weapons are loaded
+    if !weapon_library.is_loaded(&asset_server) {
+        return;
+    }
+
+    let Some(remaining) = socket.remaining_players(&config) else {
+        return;
+    };
 
     info!("Waiting for {remaining} more player(s)",);
 
@@ -68,6 +109,64 @@ pub fn start_game_when_ready(
         return;
     }
 
+    {
+        let socket = socket.0.as_mut().unwrap();
+
+        receive_lobby_messages(
+            &mut commands,
+            socket,
+            &mut pending_loadouts,
+            &mut ready_peers,
+            session_settings.is_some(),
+        );
+
+        // The host decides the session settings, which can't change once the session has started
+        let session_settings = match session_settings {
+            Some(settings) => Some((*settings).clone()),
+            None if is_host(socket) => {
+                let settings = SessionSettings::from_config(&game_settings, config.players);
+
+                info!("Hosting with session settings {settings:?}");
+
+                commands.insert_resource(settings.clone());
+                Some(settings)
+            }
+            None => None,
+        };
+
+        if let Some(settings) = session_settings.as_ref().filter(|_| is_host(socket)) {
+            broadcast(socket, &LobbyMessage::SessionSettings(settings.clone()));
+        }
+
+        // Every player shares their own loadout, which is locked in from then on
+        if !pending_loadouts.shared {
+            info!("Sharing loadout {:?}", *loadout);
+            pending_loadouts.shared = true;
+        }
+
+        broadcast(socket, &LobbyMessage::Loadout(loadout.clone()));
+
+        let Some(session_settings) = session_settings else {
+            return;
+        };
+
+        let Some(loadouts) = pending_loadouts.complete(socket, &loadout) else {
+            return;
+        };
+
+        // Every peer must confirm it has everything before anyone hands the socket to GGRS
+        broadcast(socket, &LobbyMessage::Ready);
+
+        if !ready_peers.all(socket) {
+            return;
+        }
+
+        commands.insert_resource(loadouts);
+        commands.insert_resource(session_settings.teams.clone());
+        commands.insert_resource(RollbackRng::new(session_settings.seed));
+        commands.insert_resource(MatchStats::new(config.players));
+    }
+
     info!("All peers have joined, going in-game");
 
     // consume the socket (currently required because ggrs takes ownership of its socket)
@@ -82,7 +181,7 @@ pub fn start_game_when_ready(
     let mut sess_build = SessionBuilder::<GGRSConfig>::new()
         .with_num_players(config.players)
         .with_max_prediction_window(max_prediction)
-        .with_input_delay(2)
+        .with_input_delay(INPUT_DELAY)
         .with_fps(game_settings.matchmaking.tick_rate().into())
         .expect("invalid fps");
 
@@ -102,3 +201,40 @@ pub fn start_game_when_ready(
     // transition to in-game state
     next_state.set(AppState::InGame);
 }
+
+/// Handles every pending `LobbyMessage`.
+/// Session settings are only accepted from the host, while loadouts are accepted from anyone.
+fn receive_lobby_messages(
+    commands: &mut Commands,
+    socket: &mut WebRtcSocket,
+    pending_loadouts: &mut PendingLoadouts,
+    ready_peers: &mut ReadyPeers,
+    has_session_settings: bool,
+) {
+    // The host holds the first player handle, which is only remote if the local peer isn't hosting
+    let host = match socket.players().into_iter().next() {
+        Some(ggrs::PlayerType::Remote(peer)) => Some(peer),
+        _ => None,
+    };
+
+    for (peer, message) in receive(socket) {
+        // Messages are resent until every peer is ready, so most have been seen before
+        match message {
+            Some(LobbyMessage::SessionSettings(_)) if has_session_settings => {}
+            Some(LobbyMessage::SessionSettings(settings)) if Some(&peer) == host.as_ref() => {
+                info!("Received session settings {settings:?} from host {peer:?}");
+                commands.insert_resource(settings);
+            }
+            Some(LobbyMessage::SessionSettings(_)) => {
+                warn!("Ignoring session settings from {peer:?} as they aren't the host");
+            }
+            Some(LobbyMessage::Loadout(loadout)) => {
+                debug!("Received loadout {loadout:?} from {peer:?}");
+                pending_loadouts.insert(peer, loadout);
+            }
+            Some(LobbyMessage::Ready) => ready_peers.insert(peer),
+            // Peers only start the session once ready, so anything else means they're ready too
+            None => ready_peers.insert(peer),
+        }
+    }
+}
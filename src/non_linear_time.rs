@@ -1,5 +1,10 @@
+use std::collections::VecDeque;
+
 use bevy::prelude::*;
 
+/// Number of recent occurrences remembered by `Sightings`.
+const REMEMBERED_SIGHTINGS: usize = 64;
+
 #[derive(Resource, Default, Reflect, Hash)]
 #[reflect(Resource, Hash)]
 pub struct ExactTime {
@@ -17,8 +22,86 @@ impl ExactTime {
             self.seconds += 1;
         }
     }
+
+    /// Number of ticks elapsed.
+    pub fn frame(&self) -> u32 {
+        self.seconds * self.tick_rate as u32 + self.tick as u32
+    }
+
+    /// Total time elapsed in seconds.
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.seconds as f32 + self.tick as f32 / self.tick_rate as f32
+    }
+
+    /// Duration of a single tick in seconds.
+    pub fn delta_seconds(&self) -> f32 {
+        1.0 / self.tick_rate as f32
+    }
 }
 
 pub fn track_exact_time(mut time: ResMut<ExactTime>) {
     time.tick();
 }
+
+/// Remembers what happened on recent frames, so effects outside of the simulation aren't
+/// repeated when those frames are re-simulated after a rollback.
+///
+/// Must not be rolled back itself, so it is kept in a `Local` or a resource that isn't registered
+/// for rollback.
+pub struct Sightings<K> {
+    seen: VecDeque<(u32, K)>,
+}
+
+impl<K> Default for Sightings<K> {
+    fn default() -> Self {
+        Self {
+            seen: VecDeque::new(),
+        }
+    }
+}
+
+impl<K: PartialEq> Sightings<K> {
+    /// Remembers `key` happening on `frame`, returning `false` if it has already been seen.
+    pub fn first_sighting(&mut self, frame: u32, key: K) -> bool {
+        let sighting = (frame, key);
+
+        if self.seen.contains(&sighting) {
+            return false;
+        }
+
+        self.seen.push_back(sighting);
+
+        while self.seen.len() > REMEMBERED_SIGHTINGS {
+            self.seen.pop_front();
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resimulated_frames_are_only_seen_once() {
+        let mut sightings = Sightings::default();
+
+        assert!(sightings.first_sighting(10, 'a'));
+        assert!(sightings.first_sighting(10, 'b'));
+        assert!(sightings.first_sighting(11, 'a'));
+        assert!(!sightings.first_sighting(10, 'a'));
+        assert!(!sightings.first_sighting(11, 'a'));
+    }
+
+    #[test]
+    fn old_sightings_are_forgotten() {
+        let mut sightings = Sightings::default();
+
+        for frame in 0..=REMEMBERED_SIGHTINGS as u32 {
+            assert!(sightings.first_sighting(frame, ()));
+        }
+
+        assert!(sightings.first_sighting(0, ()));
+    }
+}
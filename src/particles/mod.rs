@@ -1,7 +1,7 @@
+pub use blood::*;
 pub use smoke::*;
 pub use sparks::*;
-pub use blood::*;
 
+mod blood;
 mod smoke;
 mod sparks;
-mod blood;
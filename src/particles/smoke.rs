@@ -40,7 +40,11 @@ pub fn setup_smoke_particles(
             dimension: ShapeDimension::Volume,
         })
         .init(InitVelocitySphereModifier {
-            center: Vec3 { x: 0.0, y: -0.5, z: 0.0 },
+            center: Vec3 {
+                x: 0.0,
+                y: -0.5,
+                z: 0.0,
+            },
             speed: Value::Uniform((10., 30.)),
         })
         .init(InitLifetimeModifier {
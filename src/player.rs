@@ -1,8 +1,15 @@
 use bevy::prelude::*;
+use bevy_kira_audio::prelude::{Audio, AudioControl, AudioEmitter, AudioSource};
 use bevy_rapier3d::prelude::*;
-use std::f32::consts::*;
 
-use crate::controller::*;
+use crate::{
+    config::{CameraSettings, Config},
+    controller::*,
+    health::Health,
+    non_linear_time::{ExactTime, Sightings},
+    viewmodel::{ViewmodelAnimator, ViewmodelState},
+    water::Breath,
+};
 
 /*
     A player consists of hands, legs, a torso, and a head.
@@ -19,8 +26,20 @@ use crate::controller::*;
      * Hands to follow the head's movements (freelook).
      * The head and legs to follow the torso's transform (translation, rotation).
      * The legs to contract upwads towards the torso (crouching).
+
+    Each player also carries sensor hitboxes for their head, torso and legs, which projectiles are
+    tested against. The head and torso hitboxes are children of the torso, and the legs hitbox a
+    child of the feet. They are fitted to the torso's capsule every tick, so they follow the head
+    down when crouching. The head hitbox sits at the simulated eye height rather than on the camera,
+    whose position depends on each peer's own settings and animations.
 */
 
+/// Resting position of the right hand relative to the head.
+pub const RIGHT_HAND_OFFSET: Vec3 = Vec3::new(0.2, -0.2, -0.5);
+
+/// Resting position of the left hand relative to the head.
+pub const LEFT_HAND_OFFSET: Vec3 = Vec3::new(-0.2, -0.2, -0.5);
+
 #[derive(Component)]
 pub struct OwningPlayer(pub usize);
 
@@ -39,21 +58,86 @@ pub struct Feet;
 #[derive(Component)]
 pub struct Head;
 
+/// Radius of the head hitbox.
+const HEAD_RADIUS: f32 = 0.2;
+
+/// Radius of the torso hitbox.
+const TORSO_RADIUS: f32 = 0.45;
+
+/// Radius of the legs hitbox.
+const LEGS_RADIUS: f32 = 0.3;
+
+/// Fraction of the player's height taken up by their legs.
+const LEGS_HEIGHT_RATIO: f32 = 0.45;
+
+/// Region of a player's body covered by a hitbox.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitboxRegion {
+    Head,
+    Torso,
+    Legs,
+}
+
+impl HitboxRegion {
+    /// Factor applied to damage dealt to this region.
+    pub fn damage_multiplier(&self) -> f32 {
+        match self {
+            HitboxRegion::Head => 2.0,
+            HitboxRegion::Torso => 1.0,
+            HitboxRegion::Legs => 0.6,
+        }
+    }
+
+    /// Finds the core segment and radius of this region's hitbox, relative to the feet, for a
+    /// player whose capsule is `collider`.
+    fn fit(&self, collider: &Collider) -> Option<(Vec3, Vec3, f32)> {
+        let height = eye_height(collider, 1.0)?;
+        let eyes = eye_height(collider, SIMULATED_EYE_HEIGHT)?;
+        let waist = height * LEGS_HEIGHT_RATIO;
+
+        let (bottom, top, radius) = match self {
+            HitboxRegion::Head => (eyes - HEAD_RADIUS, eyes + HEAD_RADIUS, HEAD_RADIUS),
+            HitboxRegion::Torso => (waist, eyes - HEAD_RADIUS, TORSO_RADIUS),
+            HitboxRegion::Legs => (0.0, waist, LEGS_RADIUS),
+        };
+
+        // Regions too short for their radius shrink to a sphere in the middle
+        let middle = (bottom + top) / 2.0;
+        let a = f32::min(bottom + radius, middle);
+        let b = f32::max(top - radius, middle);
+
+        Some((Vec3::Y * a, Vec3::Y * b, radius))
+    }
+}
+
 pub struct PlayerEntity {
     pub head: Entity,
     pub torso: Entity,
     pub feet: Entity,
     pub left_hand: Entity,
     pub right_hand: Entity,
+    pub head_hitbox: Entity,
+    pub torso_hitbox: Entity,
+    pub legs_hitbox: Entity,
 }
 
-pub fn spawn_player(commands: &mut Commands, player_id: usize) -> PlayerEntity {
+pub fn spawn_player(
+    commands: &mut Commands,
+    player_id: usize,
+    movement: &MovementProfile,
+    camera: &CameraSettings,
+) -> PlayerEntity {
+    let collider = Collider::capsule(Vec3::ZERO, Vec3::Y * 2.0, 0.5);
+
     let player = PlayerEntity {
         head: commands.spawn(Head).id(),
         torso: commands.spawn(Torso).id(),
         feet: commands.spawn(Feet).id(),
         left_hand: commands.spawn(LeftHand).id(),
         right_hand: commands.spawn(RightHand).id(),
+        head_hitbox: spawn_hitbox(commands, player_id, HitboxRegion::Head),
+        torso_hitbox: spawn_hitbox(commands, player_id, HitboxRegion::Torso),
+        legs_hitbox: spawn_hitbox(commands, player_id, HitboxRegion::Legs),
     };
 
     commands
@@ -63,25 +147,30 @@ pub fn spawn_player(commands: &mut Commands, player_id: usize) -> PlayerEntity {
             OwningPlayer(player_id),
             Camera3dBundle {
                 projection: Projection::Perspective(PerspectiveProjection {
-                    fov: TAU / 5.0,
+                    fov: camera.fov_radians(),
                     ..default()
                 }),
-                transform: Transform::from_translation(Vec3 {
-                    x: 0.0,
-                    y: 1.5,
-                    z: 0.0,
-                }),
+                transform: Transform::from_translation(
+                    Vec3::Y * eye_height(&collider, camera.eye_height).unwrap_or_default(),
+                ),
                 ..default()
             },
             VisibilityBundle::default(),
+            ViewmodelAnimator::head(),
+            ViewmodelState::default(),
         ));
 
     commands
         .entity(player.torso)
-        .push_children(&[player.head, player.feet])
+        .push_children(&[
+            player.head,
+            player.feet,
+            player.head_hitbox,
+            player.torso_hitbox,
+        ])
         .insert((
             OwningPlayer(player_id),
-            Collider::capsule(Vec3::ZERO, Vec3::Y * 2.0, 0.5),
+            collider,
             Friction {
                 coefficient: 0.0,
                 combine_rule: CoefficientCombineRule::Min,
@@ -98,7 +187,12 @@ pub fn spawn_player(commands: &mut Commands, player_id: usize) -> PlayerEntity {
             AdditionalMassProperties::Mass(1.0),
             GravityScale(0.0),
             Ccd { enabled: true },
-            FpsControllerBundle::default(),
+            FpsControllerBundle {
+                controller: movement.controller(),
+                ..default()
+            },
+            Breath::default(),
+            Health::default(),
             TransformBundle::from_transform(Transform::from_translation(Vec3 {
                 x: 0.0 + 3.0 * player_id as f32,
                 y: 3.0,
@@ -107,125 +201,196 @@ pub fn spawn_player(commands: &mut Commands, player_id: usize) -> PlayerEntity {
             VisibilityBundle::default(),
         ));
 
-    commands.entity(player.feet).insert((
-        OwningPlayer(player_id),
-        TransformBundle::from_transform(Transform::from_translation(Vec3::ZERO)),
-        VisibilityBundle::default(),
-    ));
+    commands
+        .entity(player.feet)
+        .push_children(&[player.legs_hitbox])
+        .insert((
+            OwningPlayer(player_id),
+            AudioEmitter { instances: vec![] },
+            TransformBundle::from_transform(Transform::from_translation(Vec3::ZERO)),
+            VisibilityBundle::default(),
+        ));
 
     commands.entity(player.left_hand).insert((
         OwningPlayer(player_id),
-        TransformBundle::from_transform(Transform::from_translation(Vec3 {
-            x: -0.2,
-            y: -0.2,
-            z: -0.5,
-        })),
+        TransformBundle::from_transform(Transform::from_translation(LEFT_HAND_OFFSET)),
         VisibilityBundle::default(),
+        ViewmodelAnimator::left_hand(),
+        ViewmodelState::default(),
     ));
 
     commands.entity(player.right_hand).insert((
         OwningPlayer(player_id),
-        TransformBundle::from_transform(Transform::from_translation(Vec3 {
-            x: 0.2,
-            y: -0.2,
-            z: -0.5,
-        })),
+        TransformBundle::from_transform(Transform::from_translation(RIGHT_HAND_OFFSET)),
         VisibilityBundle::default(),
+        ViewmodelAnimator::right_hand(),
+        ViewmodelState::default(),
     ));
 
     player
 }
 
-pub fn head_bobbing(
-    time: Res<Time>,
-    torsos: Query<&Velocity, (With<Torso>, Without<Head>)>,
-    mut heads: Query<(&mut Transform, &Parent), (Without<Torso>, With<Head>)>,
-) {
-    let dt = time.elapsed_seconds();
+/// Spawns a sensor hitbox, which is sized and placed by `fit_hitboxes`.
+fn spawn_hitbox(commands: &mut Commands, player_id: usize, region: HitboxRegion) -> Entity {
+    commands
+        .spawn((
+            region,
+            OwningPlayer(player_id),
+            Collider::ball(HEAD_RADIUS),
+            Sensor,
+            // Hitboxes are attached to the torso's rigid body, but mustn't make it any heavier
+            ColliderMassProperties::Density(0.0),
+            TransformBundle::default(),
+        ))
+        .id()
+}
 
-    for (mut transform, torso) in heads.iter_mut() {
-        let Ok(velocity) = torsos.get(torso.get()) else {
+/// System responsible for fitting every hitbox to its player's current capsule, e.g. when crouched.
+pub fn fit_hitboxes(
+    torsos: Query<&Collider, (With<Torso>, Without<HitboxRegion>)>,
+    parents: Query<&Parent>,
+    mut hitboxes: Query<(Entity, &HitboxRegion, &mut Collider, &mut Transform), Without<Torso>>,
+) {
+    for (entity, region, mut collider, mut transform) in hitboxes.iter_mut() {
+        let Some(body) = parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| torsos.get(ancestor).ok())
+        else {
             continue;
         };
 
-        let base_translation = Vec3 {
-            x: 0.0,
-            y: 1.5,
-            z: 0.0,
+        let Some((a, b, radius)) = region.fit(body) else {
+            continue;
         };
 
-        let head_bob = Vec3 {
-            x: 0.0,
-            y: 0.02 * velocity.linvel.length(),
-            z: 0.0,
-        } * f32::sin(dt * 10.0);
-
-        transform.translation = base_translation + head_bob;
+        // Centre the collider on its transform, so the transform marks the middle of the region
+        let centre = (a + b) / 2.0;
+        let fitted = (a - centre, b - centre, radius);
+
+        let current = collider.as_capsule().map(|capsule| {
+            (
+                capsule.segment().a(),
+                capsule.segment().b(),
+                capsule.radius(),
+            )
+        });
+
+        // Avoid triggering change detection, and rebuilding the collider, when nothing changed
+        if current != Some(fitted) {
+            *collider = Collider::capsule(fitted.0, fitted.1, fitted.2);
+        }
+
+        if transform.translation != centre {
+            transform.translation = centre;
+        }
     }
 }
 
-pub fn right_hand_bobbing(
-    time: Res<Time>,
-    torsos: Query<&Velocity, (With<Torso>, Without<RightHand>, Without<Head>)>,
-    heads: Query<&Parent, (Without<Torso>, Without<RightHand>, With<Head>)>,
-    mut hands: Query<(&mut Transform, &Parent), (Without<Torso>, With<RightHand>, Without<Head>)>,
-) {
-    let dt = time.elapsed_seconds();
-
-    for (mut transform, head) in hands.iter_mut() {
-        let Ok(torso) = heads.get(head.get()) else {
-            continue;
-        };
-
-        let Ok(velocity) = torsos.get(torso.get()) else {
-            continue;
-        };
+/// Finds the transform of `entity` relative to the world, from the local transforms of it and its
+/// ancestors. Unlike `GlobalTransform` this is up to date part way through a rollback tick.
+pub fn simulated_transform(
+    entity: Entity,
+    parents: &Query<&Parent>,
+    transforms: &Query<&Transform>,
+) -> Option<Transform> {
+    let mut transform = *transforms.get(entity).ok()?;
+
+    for ancestor in parents.iter_ancestors(entity) {
+        transform = transforms.get(ancestor).ok()?.mul_transform(transform);
+    }
 
-        let base_translation = Vec3 {
-            x: 0.2,
-            y: -0.2,
-            z: -0.5,
-        };
+    Some(transform)
+}
 
-        let hand_bob = Vec3 {
-            x: 0.0,
-            y: 0.002 * velocity.linvel.length(),
-            z: 0.0,
-        } * f32::sin(dt * 10.0 + TAU / 4.0);
+#[derive(Resource)]
+pub struct MovementSounds {
+    pub footstep: Handle<AudioSource>,
+}
 
-        transform.translation = base_translation + hand_bob;
-    }
+pub fn setup_movement_sounds(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(MovementSounds {
+        footstep: assets.load("footstep.wav"),
+    });
 }
 
-pub fn left_hand_bobbing(
-    time: Res<Time>,
-    torsos: Query<&Velocity, (With<Torso>, Without<LeftHand>, Without<Head>)>,
-    heads: Query<&Parent, (Without<Torso>, Without<LeftHand>, With<Head>)>,
-    mut hands: Query<(&mut Transform, &Parent), (Without<Torso>, With<LeftHand>, Without<Head>)>,
+/// Plays footstep and landing sounds from the feet of moving players.
+///
+/// Runs in the rollback schedule, where re-simulated frames publish the same movement events
+/// again, so sounds are only played the first time a frame is simulated.
+pub fn play_movement_soundeffects(
+    time: Res<ExactTime>,
+    mut played: Local<Sightings<(Entity, bool)>>,
+    mut controller_events: EventReader<FpsControllerEvent>,
+    sounds: Res<MovementSounds>,
+    audio: Res<Audio>,
+    torsos: Query<&Children, With<Torso>>,
+    mut feet: Query<&mut AudioEmitter, With<Feet>>,
 ) {
-    let dt = time.elapsed_seconds();
-
-    for (mut transform, head) in hands.iter_mut() {
-        let Ok(torso) = heads.get(head.get()) else {
-            continue;
+    for event in controller_events.iter() {
+        let (entity, landed, volume) = match event {
+            FpsControllerEvent::Footstep { entity } => (entity, false, 0.5),
+            FpsControllerEvent::Landed { entity, .. } => (entity, true, 1.0),
+            _ => continue,
         };
 
-        let Ok(velocity) = torsos.get(torso.get()) else {
+        if !played.first_sighting(time.frame(), (*entity, landed)) {
             continue;
-        };
+        }
 
-        let base_translation = Vec3 {
-            x: -0.2,
-            y: -0.2,
-            z: -0.5,
+        let Ok(children) = torsos.get(*entity) else {
+            continue;
         };
 
-        let hand_bob = Vec3 {
-            x: 0.0,
-            y: 0.002 * velocity.linvel.length(),
-            z: 0.0,
-        } * f32::sin(dt * 10.0 + 3.0 * TAU / 4.0);
+        for child in children.iter() {
+            let Ok(mut audio_emitter) = feet.get_mut(*child) else {
+                continue;
+            };
+
+            audio_emitter.instances.push(
+                audio
+                    .play(sounds.footstep.clone_weak())
+                    .with_volume(volume)
+                    .handle(),
+            );
+        }
+    }
+}
 
-        transform.translation = base_translation + hand_bob;
+/// Applies the camera settings from `Config` to every camera.
+pub fn apply_camera_settings(
+    time: Res<Time>,
+    config: Res<Config>,
+    torsos: Query<(&OwningPlayer, &FpsControllerInput), With<Torso>>,
+    mut cameras: Query<(&mut Projection, Option<&OwningPlayer>), With<Camera3d>>,
+) {
+    /// Rate at which the field of view zooms in and out when aiming.
+    const AIM_ZOOM_RATE: f32 = 10.0;
+
+    let camera = config.graphics.camera;
+    let fov = camera.fov_radians();
+    let blend = 1.0 - f32::exp(-AIM_ZOOM_RATE * time.delta_seconds());
+
+    for (mut projection, owner) in cameras.iter_mut() {
+        let aiming = owner.map_or(false, |OwningPlayer(player)| {
+            torsos
+                .iter()
+                .any(|(OwningPlayer(owner), input)| owner == player && input.aim)
+        });
+        let target = if aiming { fov / camera.aim_zoom } else { fov };
+
+        // Avoid triggering change detection when the settings are already applied
+        if let Projection::Perspective(PerspectiveProjection { fov: current, .. }) = *projection {
+            if current == target {
+                continue;
+            }
+        }
+
+        if let Projection::Perspective(perspective) = &mut *projection {
+            perspective.fov += (target - perspective.fov) * blend;
+
+            if (target - perspective.fov).abs() < 0.001 {
+                perspective.fov = target;
+            }
+        }
     }
 }
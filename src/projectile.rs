@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    health::{DamageEvent, DamageSource},
+    lag_compensation::{Hitbox, HitboxHistory, LAG_COMPENSATION_FRAMES},
+    non_linear_time::ExactTime,
+    player::OwningPlayer,
+};
+
+/// Damage dealt by a ball for each metre per second it is travelling on impact, before the
+/// multiplier of the region it hits.
+const DAMAGE_PER_SPEED: f32 = 0.3;
+
+/// A round ball in flight. Projectiles are rollback entities, moved by the simulation every tick.
+#[derive(Component, Reflect, Default)]
+pub struct Projectile {
+    /// Velocity in metres per second.
+    pub velocity: Vec3,
+    /// Radius of the ball in metres.
+    pub radius: f32,
+    /// Fraction of its speed the ball loses per metre travelled.
+    pub drag: f32,
+    /// Seconds remaining before the ball is discarded, even if it hasn't hit anything.
+    pub lifetime: f32,
+    /// Handle of the player who fired the ball, who can't be hit by it.
+    pub owner: usize,
+}
+
+/// A projectile struck `entity`.
+pub struct ProjectileHit {
+    pub entity: Entity,
+    pub point: Vec3,
+    pub velocity: Vec3,
+    pub owner: usize,
+    /// The hitbox struck, as it was rewound to, if a player was hit.
+    pub hitbox: Option<Hitbox>,
+}
+
+/// System responsible for sweeping every projectile along its path, and reporting what it hits.
+pub fn move_projectiles(
+    mut commands: Commands,
+    time: Res<ExactTime>,
+    rapier_context: Res<RapierContext>,
+    rapier_configuration: Res<RapierConfiguration>,
+    history: Res<HitboxHistory>,
+    players: Query<&OwningPlayer>,
+    mut projectiles: Query<(Entity, &mut Transform, &mut Projectile)>,
+    mut hits: EventWriter<ProjectileHit>,
+) {
+    let dt = time.delta_seconds();
+    let rewound_frame = time.frame().saturating_sub(LAG_COMPENSATION_FRAMES);
+
+    // Players are hit where the shooter saw them, so only the scenery is tested in the present
+    let is_scenery = |collider| !players.contains(collider);
+    let filter = QueryFilter::new().exclude_sensors().predicate(&is_scenery);
+
+    for (entity, mut transform, mut projectile) in projectiles.iter_mut() {
+        projectile.lifetime -= dt;
+
+        if projectile.lifetime <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        // Drag is proportional to the square of speed, so slows the ball by distance travelled
+        let speed = projectile.velocity.length();
+        let drag = -projectile.velocity * projectile.drag * speed;
+        projectile.velocity += (rapier_configuration.gravity + drag) * dt;
+
+        let displacement = projectile.velocity * dt;
+
+        let scenery_hit = rapier_context
+            .cast_shape(
+                transform.translation,
+                Quat::IDENTITY,
+                displacement,
+                &Collider::ball(projectile.radius),
+                1.0,
+                filter,
+            )
+            .map(|(entity, toi)| (entity, None, toi.toi));
+
+        let player_hit = history
+            .cast_sphere(
+                rewound_frame,
+                transform.translation,
+                displacement,
+                projectile.radius,
+                projectile.owner,
+            )
+            .map(|(hitbox, toi)| (hitbox.player, Some(hitbox), toi));
+
+        let hit = [scenery_hit, player_hit]
+            .into_iter()
+            .flatten()
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b));
+
+        let Some((hit, hitbox, toi)) = hit else {
+            transform.translation += displacement;
+            continue;
+        };
+
+        hits.send(ProjectileHit {
+            entity: hit,
+            point: transform.translation + displacement * toi,
+            velocity: projectile.velocity,
+            owner: projectile.owner,
+            hitbox,
+        });
+
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// System responsible for injuring players struck by projectiles, depending on where they were hit.
+pub fn injure_struck_players(
+    mut hits: EventReader<ProjectileHit>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for hit in hits.iter() {
+        let Some(hitbox) = hit.hitbox else {
+            continue;
+        };
+
+        damage_events.send(DamageEvent {
+            entity: hit.entity,
+            amount: hit.velocity.length() * DAMAGE_PER_SPEED * hitbox.region.damage_multiplier(),
+            source: DamageSource::Projectile(hitbox.region),
+            instigator: Some(hit.owner),
+        });
+    }
+}
@@ -0,0 +1,186 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+/// A seeded pseudo-random number generator which is rolled back with the rest of the simulation.
+///
+/// Anything random which affects the simulation must draw from this resource, in a consistent
+/// order, so every peer (and every re-simulation after a rollback) produces the same results.
+#[derive(Resource, Default, Reflect, Hash)]
+#[reflect(Resource, Hash)]
+pub struct RollbackRng {
+    state: u64,
+}
+
+impl RollbackRng {
+    /// Creates a generator from a seed, which every peer must agree on.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Produces the next 64 random bits, using SplitMix64.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Produces a uniformly distributed value in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        // The top 24 bits are exactly representable as an `f32`
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Returns `true` with the provided probability.
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+
+    /// Produces a uniformly distributed value in `[min, max)`.
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// Deflects `forward` by up to `spread` radians, uniformly distributed across the cone.
+    pub fn cone(&mut self, forward: Vec3, spread: f32) -> Vec3 {
+        // Square root keeps directions evenly spread across the cone rather than bunched centrally
+        let deflection = spread * self.next_f32().sqrt();
+        let roll = self.range(0.0, TAU);
+
+        let tilt = Quat::from_axis_angle(forward.any_orthonormal_vector(), deflection);
+        Quat::from_axis_angle(forward, roll) * tilt * forward
+    }
+
+    /// Picks one of `choices` with probability proportional to its weight.
+    /// Returns `None` if there are no choices with a positive weight.
+    pub fn choose_weighted<'a, T>(
+        &mut self,
+        choices: &'a [T],
+        weight: impl Fn(&T) -> f32,
+    ) -> Option<&'a T> {
+        let total: f32 = choices.iter().map(|choice| weight(choice).max(0.0)).sum();
+
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut remaining = self.range(0.0, total);
+
+        // Fall back to the last positive choice in case rounding leaves some remainder
+        let mut chosen = None;
+
+        for choice in choices {
+            let weight = weight(choice).max(0.0);
+
+            if weight <= 0.0 {
+                continue;
+            }
+
+            chosen = Some(choice);
+
+            if remaining < weight {
+                break;
+            }
+
+            remaining -= weight;
+        }
+
+        chosen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bevy::reflect::GetTypeRegistration;
+
+    #[test]
+    fn equal_seeds_produce_equal_sequences() {
+        let mut a = RollbackRng::new(42);
+        let mut b = RollbackRng::new(42);
+
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn restoring_a_snapshot_replays_the_same_sequence() {
+        let mut rng = RollbackRng::new(7);
+        rng.next_u64();
+
+        // Rollback snapshots and restores resources through reflection
+        let snapshot = rng.clone_value();
+        let before = (0..10).map(|_| rng.next_u64()).collect::<Vec<_>>();
+
+        rng.apply(&*snapshot);
+        let after = (0..10).map(|_| rng.next_u64()).collect::<Vec<_>>();
+
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn restoring_a_snapshot_in_a_world_replays_the_same_sequence() {
+        let mut world = World::new();
+        world.insert_resource(RollbackRng::new(99));
+
+        let registration = RollbackRng::get_type_registration();
+        let reflect_resource = registration.data::<ReflectResource>().unwrap();
+
+        let snapshot = reflect_resource.reflect(&world).unwrap().clone_value();
+        let before = world.resource_mut::<RollbackRng>().next_u64();
+
+        reflect_resource.apply(&mut world, &*snapshot);
+        let after = world.resource_mut::<RollbackRng>().next_u64();
+
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn range_stays_within_bounds() {
+        let mut rng = RollbackRng::new(1);
+
+        for _ in 0..1000 {
+            let value = rng.range(-2.0, 3.0);
+            assert!((-2.0..3.0).contains(&value), "{value} is out of range");
+        }
+    }
+
+    #[test]
+    fn cone_stays_within_spread() {
+        let mut rng = RollbackRng::new(2);
+        let forward = Vec3::new(1.0, 2.0, -3.0).normalize();
+
+        for _ in 0..1000 {
+            let direction = rng.cone(forward, 0.1);
+            assert!(direction.angle_between(forward) <= 0.1 + 1e-4);
+            assert!((direction.length() - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn weighted_choice_never_picks_weightless_choices() {
+        let mut rng = RollbackRng::new(3);
+        let choices = [
+            ("never", 0.0),
+            ("rare", 1.0),
+            ("common", 9.0),
+            ("negative", -5.0),
+        ];
+
+        for _ in 0..1000 {
+            let (name, _) = rng
+                .choose_weighted(&choices, |(_, weight)| *weight)
+                .unwrap();
+            assert!(*name == "rare" || *name == "common");
+        }
+
+        assert!(rng
+            .choose_weighted(&choices[..1], |(_, weight)| *weight)
+            .is_none());
+    }
+}
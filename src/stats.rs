@@ -0,0 +1,238 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::Serialize;
+
+use crate::{
+    firearm::{FirearmEvent, Fired},
+    health::{DamageSource, Injured},
+    player::OwningPlayer,
+    projectile::ProjectileHit,
+    team::Teams,
+};
+
+/// What a single player has done this match.
+#[derive(Reflect, FromReflect, Serialize, Default, Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct PlayerStats {
+    pub kills: u32,
+    pub deaths: u32,
+    pub shots_fired: u32,
+    /// Shots which struck another player.
+    pub hits: u32,
+}
+
+impl PlayerStats {
+    /// Fraction of shots fired which struck another player.
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            return 0.0;
+        }
+
+        self.hits as f32 / self.shots_fired as f32
+    }
+}
+
+/// The stats of every player this match, indexed by player handle.
+///
+/// Stats are updated by the simulation and rolled back with it. The number of players is fixed
+/// when the match starts, as restoring a snapshot never shrinks the list.
+#[derive(Resource, Default, Reflect, Hash)]
+#[reflect(Resource, Hash)]
+pub struct MatchStats {
+    players: Vec<PlayerStats>,
+}
+
+impl MatchStats {
+    pub fn new(players: usize) -> Self {
+        Self {
+            players: vec![PlayerStats::default(); players],
+        }
+    }
+
+    pub fn get(&self, player_handle: usize) -> Option<&PlayerStats> {
+        self.players.get(player_handle)
+    }
+
+    /// Lists the stats of every player, along with their handle.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &PlayerStats)> {
+        self.players.iter().enumerate()
+    }
+
+    fn update(&mut self, player_handle: usize, update: impl FnOnce(&mut PlayerStats)) {
+        match self.players.get_mut(player_handle) {
+            Some(stats) => update(stats),
+            None => warn!("No stats are kept for player {player_handle}"),
+        }
+    }
+}
+
+/// System responsible for counting shots, hits, kills and deaths.
+pub fn record_stats(
+    mut fired_events: EventReader<FirearmEvent<Fired>>,
+    mut hit_events: EventReader<ProjectileHit>,
+    mut injured_events: EventReader<Injured>,
+    hands: Query<&OwningPlayer>,
+    mut stats: ResMut<MatchStats>,
+) {
+    for fired_event in fired_events.iter() {
+        let Ok(OwningPlayer(shooter)) = hands.get(fired_event.entity) else {
+            continue;
+        };
+
+        stats.update(*shooter, |stats| stats.shots_fired += 1);
+    }
+
+    for hit in hit_events.iter() {
+        let Some(hitbox) = hit.hitbox else {
+            continue;
+        };
+
+        if hitbox.owner != hit.owner {
+            stats.update(hit.owner, |stats| stats.hits += 1);
+        }
+    }
+
+    for injured in injured_events.iter().filter(|injured| injured.killed) {
+        let Some(victim) = injured.victim else {
+            continue;
+        };
+
+        stats.update(victim, |stats| stats.deaths += 1);
+
+        // Falling, drowning and bursting barrels don't credit anyone, and nor does killing yourself
+        let credited = !matches!(
+            injured.source,
+            DamageSource::Fall | DamageSource::Drowning | DamageSource::BarrelBurst
+        );
+
+        match injured.instigator {
+            Some(killer) if credited && killer != victim => {
+                stats.update(killer, |stats| stats.kills += 1);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The stats of a single player, as written to the match summary.
+#[derive(Serialize)]
+struct PlayerSummary {
+    handle: usize,
+    team: Option<usize>,
+    #[serde(flatten)]
+    stats: PlayerStats,
+    accuracy: f32,
+}
+
+/// The results of a match, written alongside the settings file when the match ends.
+#[derive(Serialize)]
+struct MatchSummary {
+    /// When the match ended, in seconds since the Unix epoch.
+    finished_at: u64,
+    players: Vec<PlayerSummary>,
+}
+
+impl MatchSummary {
+    fn new(stats: &MatchStats, teams: &Teams) -> Self {
+        let finished_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+
+        let players = stats
+            .iter()
+            .map(|(handle, stats)| PlayerSummary {
+                handle,
+                team: teams.team(handle),
+                stats: *stats,
+                accuracy: stats.accuracy(),
+            })
+            .collect();
+
+        Self {
+            finished_at,
+            players,
+        }
+    }
+
+    fn try_save(&self) -> Result<(), &'static str> {
+        let file_name = format!("match_summary_{}.json", self.finished_at);
+
+        log::trace!("Saving Match Summary to '{}'", file_name);
+
+        let file = File::create(file_name).map_err(|_| "Cannot Create Match Summary File")?;
+
+        let buf_writer = BufWriter::new(file);
+
+        serde_json::to_writer_pretty(buf_writer, self)
+            .map_err(|_| "Cannot Write Match Summary File")?;
+
+        Ok(())
+    }
+}
+
+/// Writes a summary of the match, unless it never started.
+fn save_match_summary(stats: &MatchStats, teams: &Teams) {
+    // Stats are only kept for players once a match has started
+    if stats.iter().next().is_none() {
+        return;
+    }
+
+    if let Err(error) = MatchSummary::new(stats, teams).try_save() {
+        log::error!("Unable to save match summary: {error}");
+    }
+}
+
+/// System responsible for writing a summary of the match once it ends, clearing the stats so the
+/// same match isn't summarised again.
+pub fn save_match_summary_on_match_end(mut stats: ResMut<MatchStats>, teams: Res<Teams>) {
+    save_match_summary(&stats, &teams);
+
+    *stats = MatchStats::default();
+}
+
+/// System responsible for writing a summary of the match when the game is closed part way
+/// through one, as closing the game doesn't end the match.
+pub fn save_match_summary_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    stats: Res<MatchStats>,
+    teams: Res<Teams>,
+) {
+    if exit_events.iter().next().is_none() {
+        return;
+    }
+
+    save_match_summary(&stats, &teams);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accuracy_is_hits_per_shot() {
+        let stats = PlayerStats {
+            shots_fired: 4,
+            hits: 1,
+            ..default()
+        };
+
+        assert_eq!(stats.accuracy(), 0.25);
+        assert_eq!(PlayerStats::default().accuracy(), 0.0);
+    }
+
+    #[test]
+    fn restoring_a_snapshot_undoes_later_stats() {
+        let mut stats = MatchStats::new(2);
+        let snapshot = stats.clone_value();
+
+        stats.update(1, |stats| stats.kills += 1);
+        stats.apply(&*snapshot);
+
+        assert_eq!(stats.get(1), Some(&PlayerStats::default()));
+        assert_eq!(stats.iter().count(), 2);
+    }
+}
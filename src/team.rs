@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How players are grouped against each other.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    /// Every player is on their own.
+    #[default]
+    FreeForAll,
+    /// Players are split evenly between two teams.
+    TeamVsTeam,
+}
+
+/// How much damage players deal to members of their own team.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum FriendlyFire {
+    /// Allies can't be injured.
+    #[default]
+    Off,
+    /// Allies take full damage.
+    On,
+    /// Allies take the provided fraction of the damage.
+    Reduced(f32),
+}
+
+impl FriendlyFire {
+    /// Factor applied to damage dealt to an ally.
+    pub fn damage_multiplier(&self) -> f32 {
+        match self {
+            FriendlyFire::Off => 0.0,
+            FriendlyFire::On => 1.0,
+            FriendlyFire::Reduced(fraction) => fraction.clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Colours worn by each team in team-vs-team.
+const TEAM_COLORS: [Color; 2] = [Color::rgb(0.7, 0.1, 0.1), Color::rgb(0.1, 0.2, 0.6)];
+
+/// Colours worn by players in free-for-all, repeating if there are more players than colours.
+const FREE_FOR_ALL_COLORS: [Color; 4] = [
+    Color::rgb(0.3, 0.8, 0.3),
+    Color::rgb(0.8, 0.7, 0.2),
+    Color::rgb(0.5, 0.3, 0.7),
+    Color::rgb(0.8, 0.4, 0.1),
+];
+
+/// The team of every player, chosen by the host and agreed on before the session starts.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Teams {
+    pub mode: GameMode,
+    pub friendly_fire: FriendlyFire,
+    /// Team of each player, indexed by player handle.
+    assignments: Vec<usize>,
+}
+
+impl Teams {
+    /// Assigns `players` to teams, alternating between the teams so they stay balanced.
+    pub fn assign(mode: GameMode, friendly_fire: FriendlyFire, players: usize) -> Self {
+        let assignments = match mode {
+            GameMode::FreeForAll => (0..players).collect(),
+            GameMode::TeamVsTeam => (0..players)
+                .map(|player| player % TEAM_COLORS.len())
+                .collect(),
+        };
+
+        Self {
+            mode,
+            friendly_fire,
+            assignments,
+        }
+    }
+
+    /// The team of a player, or `None` if they weren't assigned one.
+    pub fn team(&self, player_handle: usize) -> Option<usize> {
+        self.assignments.get(player_handle).copied()
+    }
+
+    /// Checks if two players are on the same team. Every player is their own ally.
+    pub fn are_allies(&self, a: usize, b: usize) -> bool {
+        if a == b {
+            return true;
+        }
+
+        match self.mode {
+            GameMode::FreeForAll => false,
+            GameMode::TeamVsTeam => self.team(a).is_some() && self.team(a) == self.team(b),
+        }
+    }
+
+    /// Factor applied to damage `instigator` deals to `victim`, according to the friendly-fire
+    /// policy. Players always take full damage from their own actions.
+    pub fn damage_multiplier(&self, instigator: usize, victim: usize) -> f32 {
+        if instigator != victim && self.are_allies(instigator, victim) {
+            self.friendly_fire.damage_multiplier()
+        } else {
+            1.0
+        }
+    }
+
+    /// The colour a player wears.
+    pub fn color(&self, player_handle: usize) -> Color {
+        let team = self.team(player_handle).unwrap_or(player_handle);
+
+        match self.mode {
+            GameMode::FreeForAll => FREE_FOR_ALL_COLORS[team % FREE_FOR_ALL_COLORS.len()],
+            GameMode::TeamVsTeam => TEAM_COLORS[team % TEAM_COLORS.len()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn team_vs_team_splits_players_evenly() {
+        let teams = Teams::assign(GameMode::TeamVsTeam, FriendlyFire::Off, 5);
+
+        let red = (0..5)
+            .filter(|player| teams.team(*player) == Some(0))
+            .count();
+        let blue = (0..5)
+            .filter(|player| teams.team(*player) == Some(1))
+            .count();
+
+        assert_eq!((red, blue), (3, 2));
+        assert!(teams.are_allies(0, 2));
+        assert!(!teams.are_allies(0, 1));
+    }
+
+    #[test]
+    fn free_for_all_has_no_allies() {
+        let teams = Teams::assign(GameMode::FreeForAll, FriendlyFire::On, 4);
+
+        assert!(!teams.are_allies(0, 1));
+        assert!(!teams.are_allies(2, 3));
+        assert_eq!(teams.damage_multiplier(0, 1), 1.0);
+    }
+
+    #[test]
+    fn friendly_fire_policy_scales_damage_between_allies_only() {
+        let off = Teams::assign(GameMode::TeamVsTeam, FriendlyFire::Off, 4);
+        let reduced = Teams::assign(GameMode::TeamVsTeam, FriendlyFire::Reduced(0.25), 4);
+
+        assert_eq!(off.damage_multiplier(0, 2), 0.0);
+        assert_eq!(off.damage_multiplier(0, 1), 1.0);
+        assert_eq!(reduced.damage_multiplier(0, 2), 0.25);
+
+        // Self-inflicted damage, such as a burst barrel, is never reduced
+        assert_eq!(off.damage_multiplier(3, 3), 1.0);
+    }
+}
@@ -0,0 +1,425 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    config::Config,
+    controller::{eye_height, FpsController, FpsControllerEvent, FpsControllerInput},
+    firearm::{FirearmEvent, Fired},
+    non_linear_time::{ExactTime, Sightings},
+    player::{OwningPlayer, Torso, LEFT_HAND_OFFSET, RIGHT_HAND_OFFSET},
+};
+
+/*
+    The viewmodel animator procedurally moves the head and hands of a player on top of their
+    resting pose. It is purely cosmetic: it runs outside of the rollback schedule, its state is
+    never rolled back, and nothing in the simulation reads the offsets it applies.
+
+    Landings and shots are recorded from inside the rollback schedule instead, once per simulated
+    frame, so frames re-simulated after a rollback don't dip or kick the viewmodel again.
+*/
+
+/// Where a body part rests before any procedural animation is applied.
+#[derive(Clone, Copy)]
+pub enum RestPose {
+    /// At the player's eye height, following their crouching.
+    Eyes,
+    /// At a fixed offset from the parent.
+    Offset(Vec3),
+}
+
+/// Describes how a body part is procedurally animated.
+#[derive(Component, Clone)]
+pub struct ViewmodelAnimator {
+    pub rest: RestPose,
+    /// Bob displacement per metre per second of player speed.
+    pub bob: Vec3,
+    /// Bob cycles per second, in radians.
+    pub bob_frequency: f32,
+    /// Offset into the bob cycle, in radians.
+    pub bob_phase: f32,
+    /// Displacement per radian of yaw (x) and pitch (y) the player turns.
+    pub sway: Vec2,
+    /// Displacement at the deepest point of a landing.
+    pub landing_dip: Vec3,
+    /// Displacement while sprinting.
+    pub sprint: Vec3,
+    /// Displacement while aiming down the sights.
+    pub aim: Vec3,
+    /// Displacement at the peak of a recoil kick.
+    pub recoil: Vec3,
+}
+
+impl ViewmodelAnimator {
+    pub fn head() -> Self {
+        Self {
+            rest: RestPose::Eyes,
+            bob: Vec3::new(0.0, 0.02, 0.0),
+            bob_frequency: 10.0,
+            bob_phase: 0.0,
+            sway: Vec2::ZERO,
+            landing_dip: Vec3::new(0.0, -0.15, 0.0),
+            sprint: Vec3::ZERO,
+            aim: Vec3::ZERO,
+            recoil: Vec3::new(0.0, 0.0, 0.03),
+        }
+    }
+
+    pub fn right_hand() -> Self {
+        Self {
+            rest: RestPose::Offset(RIGHT_HAND_OFFSET),
+            bob: Vec3::new(0.0, 0.002, 0.0),
+            bob_frequency: 10.0,
+            bob_phase: TAU / 4.0,
+            sway: Vec2::new(0.05, 0.05),
+            landing_dip: Vec3::new(0.0, -0.05, 0.0),
+            sprint: Vec3::new(-0.1, -0.1, 0.1),
+            aim: Vec3::new(-0.2, 0.1, 0.1),
+            recoil: Vec3::new(0.0, 0.02, 0.12),
+        }
+    }
+
+    pub fn left_hand() -> Self {
+        Self {
+            rest: RestPose::Offset(LEFT_HAND_OFFSET),
+            bob_phase: 3.0 * TAU / 4.0,
+            sprint: Vec3::new(0.05, -0.1, 0.1),
+            aim: Vec3::new(0.1, 0.05, 0.1),
+            recoil: Vec3::new(0.0, 0.01, 0.06),
+            ..Self::right_hand()
+        }
+    }
+}
+
+/// Cosmetic animation state of a body part. Deliberately not registered for rollback.
+#[derive(Component, Default)]
+pub struct ViewmodelState {
+    sway: Vec2,
+    dip: f32,
+    sprint: f32,
+    aim: f32,
+    recoil: f32,
+    last_look: Option<Vec2>,
+}
+
+impl ViewmodelState {
+    /// Rate at which sway, dips and recoil return to rest.
+    const RECOVERY_RATE: f32 = 8.0;
+    /// Rate at which the sprint pose is blended in and out.
+    const SPRINT_BLEND_RATE: f32 = 5.0;
+    /// Rate at which the aiming pose is blended in and out.
+    const AIM_BLEND_RATE: f32 = 6.0;
+    /// Landing speed which produces the deepest dip.
+    const MAX_DIP_SPEED: f32 = 20.0;
+    /// Furthest the accumulated sway can reach, in radians.
+    const MAX_SWAY: f32 = 0.5;
+}
+
+/// Landings and shots which have yet to be animated. Deliberately not registered for rollback.
+#[derive(Resource, Default)]
+pub struct ViewmodelTriggers {
+    /// Players who landed, and how hard they hit the ground.
+    landings: Vec<(usize, f32)>,
+    /// Players who fired a shot.
+    recoils: Vec<usize>,
+    /// Landings and shots already recorded, as who landed or fired and whether they fired.
+    recorded: Sightings<(Entity, bool)>,
+}
+
+/// System responsible for recording the landings and shots viewmodels react to, the first time
+/// each frame is simulated.
+pub fn record_viewmodel_triggers(
+    time: Res<ExactTime>,
+    mut triggers: ResMut<ViewmodelTriggers>,
+    mut controller_events: EventReader<FpsControllerEvent>,
+    mut fired_events: EventReader<FirearmEvent<Fired>>,
+    owners: Query<&OwningPlayer>,
+) {
+    let frame = time.frame();
+
+    for event in controller_events.iter() {
+        let FpsControllerEvent::Landed {
+            entity,
+            impact_speed,
+        } = event
+        else {
+            continue;
+        };
+
+        if !triggers.recorded.first_sighting(frame, (*entity, false)) {
+            continue;
+        }
+
+        if let Ok(OwningPlayer(player)) = owners.get(*entity) {
+            triggers.landings.push((*player, *impact_speed));
+        }
+    }
+
+    for event in fired_events.iter() {
+        if !triggers
+            .recorded
+            .first_sighting(frame, (event.entity, true))
+        {
+            continue;
+        }
+
+        if let Ok(OwningPlayer(player)) = owners.get(event.entity) {
+            triggers.recoils.push(*player);
+        }
+    }
+}
+
+/// System responsible for procedurally animating the head and hands of every player.
+pub fn animate_viewmodels(
+    time: Res<Time>,
+    exact_time: Res<ExactTime>,
+    config: Res<Config>,
+    mut triggers: ResMut<ViewmodelTriggers>,
+    torsos: Query<
+        (
+            &OwningPlayer,
+            &Velocity,
+            &Collider,
+            &FpsController,
+            &FpsControllerInput,
+        ),
+        With<Torso>,
+    >,
+    mut parts: Query<(
+        &mut Transform,
+        &ViewmodelAnimator,
+        &mut ViewmodelState,
+        &OwningPlayer,
+    )>,
+) {
+    let dt = time.delta_seconds();
+    let phase = exact_time.elapsed_seconds();
+    let camera = config.graphics.camera;
+    let recovery = f32::exp(-ViewmodelState::RECOVERY_RATE * dt);
+
+    let landings = std::mem::take(&mut triggers.landings);
+    let recoils = std::mem::take(&mut triggers.recoils);
+
+    for (mut transform, animator, mut state, OwningPlayer(player)) in parts.iter_mut() {
+        let Some((_, velocity, collider, controller, input)) = torsos
+            .iter()
+            .find(|(OwningPlayer(owner), ..)| owner == player)
+        else {
+            continue;
+        };
+
+        let rest = match animator.rest {
+            RestPose::Eyes => {
+                let Some(eye_height) = eye_height(collider, camera.eye_height) else {
+                    continue;
+                };
+
+                Vec3::Y * eye_height
+            }
+            RestPose::Offset(offset) => offset,
+        };
+
+        let speed = velocity.linvel.length();
+
+        // Sway lags behind changes in where the player is looking
+        let look = Vec2::new(controller.yaw, controller.pitch);
+        let mut look_delta = state.last_look.map_or(Vec2::ZERO, |last| look - last);
+        look_delta.x = (look_delta.x + PI).rem_euclid(TAU) - PI; // Yaw wraps around
+        state.last_look = Some(look);
+        state.sway =
+            ((state.sway + look_delta) * recovery).clamp_length_max(ViewmodelState::MAX_SWAY);
+
+        // Landings dip by how hard the player hit the ground
+        for (_, impact_speed) in landings.iter().filter(|(owner, _)| owner == player) {
+            let dip = impact_speed / ViewmodelState::MAX_DIP_SPEED;
+            state.dip = f32::max(state.dip, dip).min(1.0);
+        }
+        state.dip *= recovery;
+
+        // The sprint pose blends in while actually running
+        let sprinting = input.sprint && speed > controller.walk_speed;
+        let sprint_target = if sprinting { 1.0 } else { 0.0 };
+        let sprint_step = ViewmodelState::SPRINT_BLEND_RATE * dt;
+        state.sprint += (sprint_target - state.sprint).clamp(-sprint_step, sprint_step);
+
+        // Raising the sights takes priority over the sprint pose
+        let aim_target = if input.aim { 1.0 } else { 0.0 };
+        let aim_step = ViewmodelState::AIM_BLEND_RATE * dt;
+        state.aim += (aim_target - state.aim).clamp(-aim_step, aim_step);
+        state.sprint = state.sprint.min(1.0 - state.aim);
+
+        // Firing kicks every part of the shooter
+        if recoils.contains(player) {
+            state.recoil = 1.0;
+        }
+        state.recoil *= recovery;
+
+        let bob =
+            animator.bob * speed * f32::sin(phase * animator.bob_frequency + animator.bob_phase);
+        let sway = Vec3::new(
+            state.sway.x * animator.sway.x,
+            -state.sway.y * animator.sway.y,
+            0.0,
+        );
+
+        transform.translation = rest
+            + (bob + animator.landing_dip * state.dip) * camera.view_bob
+            + sway
+            + animator.sprint * state.sprint
+            + animator.aim * state.aim
+            + animator.recoil * state.recoil;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::schedule::ExecutorKind;
+
+    use super::*;
+
+    const PLAYER: usize = 0;
+
+    /// Creates a world with a standing player, returning the entities of their torso, animated
+    /// head and firearm.
+    fn world_with_player() -> (World, Entity, Entity, Entity) {
+        let mut world = World::new();
+
+        let mut time = Time::default();
+        time.update_with_instant(time.startup());
+        time.update_with_instant(time.startup() + Duration::from_millis(16));
+        world.insert_resource(time);
+        world.insert_resource(ExactTime {
+            tick_rate: 60,
+            tick: 0,
+            seconds: 0,
+        });
+        world.insert_resource(Config::default());
+        world.init_resource::<ViewmodelTriggers>();
+        world.init_resource::<Events<FpsControllerEvent>>();
+        world.init_resource::<Events<FirearmEvent<Fired>>>();
+
+        let torso = world
+            .spawn((
+                Torso,
+                OwningPlayer(PLAYER),
+                Velocity::zero(),
+                Collider::capsule(Vec3::Y * 0.5, Vec3::Y * 2.0, 0.5),
+                FpsController::default(),
+                FpsControllerInput::default(),
+            ))
+            .id();
+        let head = world
+            .spawn((
+                Transform::default(),
+                ViewmodelAnimator::head(),
+                ViewmodelState::default(),
+                OwningPlayer(PLAYER),
+            ))
+            .id();
+        let firearm = world.spawn(OwningPlayer(PLAYER)).id();
+
+        (world, torso, head, firearm)
+    }
+
+    fn schedule_with<M>(system: impl IntoSystemConfig<M>) -> Schedule {
+        let mut schedule = Schedule::new();
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        schedule.add_system(system);
+        schedule
+    }
+
+    /// Publishes the events of a frame in which the player landed and fired.
+    fn land_and_fire(world: &mut World, torso: Entity, firearm: Entity) {
+        world.send_event(FpsControllerEvent::Landed {
+            entity: torso,
+            impact_speed: 10.0,
+        });
+        world.send_event(FirearmEvent {
+            details: Fired,
+            entity: firearm,
+        });
+    }
+
+    fn head_translation(world: &World, head: Entity) -> Vec3 {
+        world.get::<Transform>(head).unwrap().translation
+    }
+
+    fn eyes(world: &World, torso: Entity) -> Vec3 {
+        let collider = world.get::<Collider>(torso).unwrap();
+        let camera = world.resource::<Config>().graphics.camera;
+
+        Vec3::Y * eye_height(collider, camera.eye_height).unwrap()
+    }
+
+    #[test]
+    fn resimulated_frames_are_only_recorded_once() {
+        let (mut world, torso, _, firearm) = world_with_player();
+        let mut schedule = schedule_with(record_viewmodel_triggers);
+
+        // The frame is simulated, rolled back and simulated again
+        for _ in 0..2 {
+            land_and_fire(&mut world, torso, firearm);
+            schedule.run(&mut world);
+        }
+
+        let triggers = world.resource::<ViewmodelTriggers>();
+        assert_eq!(triggers.landings, vec![(PLAYER, 10.0)]);
+        assert_eq!(triggers.recoils, vec![PLAYER]);
+
+        // The next frame is new, so it is recorded
+        world.resource_mut::<ExactTime>().tick();
+        land_and_fire(&mut world, torso, firearm);
+        schedule.run(&mut world);
+
+        let triggers = world.resource::<ViewmodelTriggers>();
+        assert_eq!(triggers.landings.len(), 2);
+        assert_eq!(triggers.recoils.len(), 2);
+    }
+
+    #[test]
+    fn heads_rest_at_the_eyes() {
+        let (mut world, torso, head, _) = world_with_player();
+
+        schedule_with(animate_viewmodels).run(&mut world);
+
+        assert_eq!(head_translation(&world, head), eyes(&world, torso));
+    }
+
+    #[test]
+    fn landing_dips_the_head_once() {
+        let (mut world, torso, head, _) = world_with_player();
+        let mut schedule = schedule_with(animate_viewmodels);
+        world
+            .resource_mut::<ViewmodelTriggers>()
+            .landings
+            .push((PLAYER, 10.0));
+
+        schedule.run(&mut world);
+        let dipped = head_translation(&world, head);
+        schedule.run(&mut world);
+
+        let eyes = eyes(&world, torso);
+        assert!(dipped.y < eyes.y, "Head: {dipped}, eyes: {eyes}");
+        // The dip recovers rather than being triggered again
+        assert!(head_translation(&world, head).y > dipped.y);
+        assert!(world.resource::<ViewmodelTriggers>().landings.is_empty());
+    }
+
+    #[test]
+    fn firing_kicks_the_head_back() {
+        let (mut world, torso, head, _) = world_with_player();
+        world
+            .resource_mut::<ViewmodelTriggers>()
+            .recoils
+            .push(PLAYER);
+
+        schedule_with(animate_viewmodels).run(&mut world);
+
+        let kicked = head_translation(&world, head) - eyes(&world, torso);
+        assert!(kicked.z > 0.0, "Kick: {kicked}");
+        assert!(world.resource::<ViewmodelTriggers>().recoils.is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::{gltf::GltfExtras, prelude::*, render::primitives::Aabb};
+
+use crate::{controller::FpsController, non_linear_time::ExactTime};
+
+/// Marks a sensor collider as a body of water players can swim in.
+#[derive(Component)]
+pub struct WaterVolume {
+    /// World-space height of the surface of the water.
+    pub surface: f32,
+}
+
+impl WaterVolume {
+    /// Custom property on a glTF node which marks it as a body of water.
+    const EXTRAS_KEY: &str = "water";
+
+    /// Checks the custom properties of a glTF node for the water marker.
+    pub fn is_water(extras: &GltfExtras) -> bool {
+        let Ok(extras) = serde_json::from_str::<serde_json::Value>(&extras.value) else {
+            log::warn!("Unable to parse glTF extras: {}", extras.value);
+            return false;
+        };
+
+        match extras.get(Self::EXTRAS_KEY) {
+            Some(serde_json::Value::Bool(value)) => *value,
+            Some(serde_json::Value::Number(value)) => value.as_f64().unwrap_or(0.0) != 0.0,
+            Some(serde_json::Value::String(value)) => value == "true" || value == "1",
+            _ => false,
+        }
+    }
+
+    /// Creates a water volume filling the provided bounding box, placed with `transform`.
+    /// Returns the volume alongside the transform of its centre.
+    pub fn from_aabb(aabb: &Aabb, transform: Transform) -> (Self, Transform) {
+        let transform = transform * Transform::from_translation(aabb.center.into());
+        let surface = transform.translation.y + aabb.half_extents.y * transform.scale.y;
+
+        (Self { surface }, transform)
+    }
+}
+
+/// Component tracking how long a player can remain underwater.
+#[derive(Component, Reflect)]
+pub struct Breath {
+    /// Seconds of breath held when full.
+    pub capacity: f32,
+    /// Seconds of breath remaining.
+    pub remaining: f32,
+    /// Whether the player's eyes were underwater on the previous tick.
+    pub underwater: bool,
+}
+
+impl Default for Breath {
+    fn default() -> Self {
+        Self {
+            capacity: 10.0,
+            remaining: 10.0,
+            underwater: false,
+        }
+    }
+}
+
+impl Breath {
+    /// How many seconds of breath are recovered per second above water.
+    const RECOVERY_RATE: f32 = 4.0;
+}
+
+/// Hooks for gameplay to react to a player's breath.
+pub enum BreathEvent {
+    /// The player's eyes have gone below the surface.
+    Submerged { entity: Entity },
+    /// The player's eyes have returned above the surface.
+    Surfaced { entity: Entity },
+    /// The player is out of breath, sent every tick until they surface.
+    Drowning { entity: Entity },
+}
+
+/// System responsible for holding and recovering player breath while swimming.
+pub fn track_breath(
+    time: Res<ExactTime>,
+    mut query: Query<(Entity, &FpsController, &mut Breath)>,
+    mut breath_events: EventWriter<BreathEvent>,
+) {
+    let dt = time.delta_seconds();
+
+    for (entity, controller, mut breath) in query.iter_mut() {
+        let underwater = controller.submersion >= 1.0;
+
+        match (breath.underwater, underwater) {
+            (false, true) => breath_events.send(BreathEvent::Submerged { entity }),
+            (true, false) => breath_events.send(BreathEvent::Surfaced { entity }),
+            _ => {}
+        }
+
+        breath.underwater = underwater;
+
+        if underwater {
+            breath.remaining = f32::max(breath.remaining - dt, 0.0);
+
+            if breath.remaining <= 0.0 {
+                breath_events.send(BreathEvent::Drowning { entity });
+            }
+        } else {
+            breath.remaining = f32::min(
+                breath.remaining + Breath::RECOVERY_RATE * dt,
+                breath.capacity,
+            );
+        }
+    }
+}